@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+
+use types::*;
+
+
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f64, // Seconds.
+    pub transform: Transform,
+}
+
+impl Keyframe {
+    pub fn new(time: f64, transform: Transform) -> Keyframe {
+        Keyframe {
+            time: time,
+            transform: transform,
+        }
+    }
+}
+
+
+// A transform animated between keyframes. Translation and scale are
+// interpolated linearly and rotation by slerp.
+#[derive(Clone)]
+pub struct AnimationClip {
+    pub keyframes: Vec<Keyframe>, // Sorted by time.
+}
+
+impl AnimationClip {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> AnimationClip {
+        keyframes.sort_by(|a, b| {
+            a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal)
+        });
+        AnimationClip { keyframes: keyframes }
+    }
+
+    pub fn duration(&self) -> f64 {
+        match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.,
+        }
+    }
+
+    // Holds the first and last keyframes outside the clip's time range.
+    pub fn sample(&self, time: f64) -> Transform {
+        let frames = &self.keyframes;
+        if frames.is_empty() { return Transform::identity() }
+        if time <= frames[0].time { return frames[0].transform }
+
+        for pair in frames.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if time < b.time {
+                let t = (time - a.time) / (b.time - a.time);
+                return interpolate(a.transform, b.transform, t);
+            }
+        }
+        frames[frames.len() - 1].transform
+    }
+
+    // Repeats the clip forever, starting at its first keyframe.
+    pub fn sample_looping(&self, time: f64) -> Transform {
+        let duration = self.duration();
+        if duration <= 0. { return self.sample(time) }
+
+        let start = self.keyframes[0].time;
+        let offset = (time - start) % duration;
+        let offset = if offset < 0. { offset + duration } else { offset };
+        self.sample(start + offset)
+    }
+}
+
+fn interpolate(a: Transform, b: Transform, t: f64) -> Transform {
+    let (ta, ra, sa) = a.decompose();
+    let (tb, rb, sb) = b.decompose();
+    Transform::from_parts(
+        ta * (1. - t) + tb * t,
+        ra.slerp(rb, t),
+        sa * (1. - t) + sb * t
+    )
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn assert_same(a: Transform, b: Transform) {
+        let (a, b) = (a.to_array(), b.to_array());
+        for (ra, rb) in a.iter().zip(b.iter()) {
+            for (&x, &y) in ra.iter().zip(rb.iter()) {
+                assert!((x - y).abs() < 1e-9, "{:?} is not {:?}", a, b);
+            }
+        }
+    }
+
+    fn clip() -> AnimationClip {
+        let turned = Transform::from_parts(
+            pt![4., 0., -2.],
+            Quaternion::from_axis_angle(pt![0., 1., 0.], PI / 2.),
+            pt![3., 3., 3.]
+        );
+        // Given out of order; `new` sorts them.
+        AnimationClip::new(vec![
+            Keyframe::new(3., Transform::translate(pt![0., 6., 0.])),
+            Keyframe::new(1., Transform::identity()),
+            Keyframe::new(2., turned),
+        ])
+    }
+
+    #[test]
+    fn sample_at_keyframes() {
+        let clip = clip();
+        assert_eq!(clip.duration(), 2.);
+        for k in &clip.keyframes {
+            assert_same(clip.sample(k.time), k.transform);
+        }
+        // Held outside the clip.
+        assert_same(clip.sample(-5.), Transform::identity());
+        assert_same(clip.sample(10.), Transform::translate(pt![0., 6., 0.]));
+    }
+
+    #[test]
+    fn sample_between_keyframes() {
+        let clip = clip();
+        let half_turn = Transform::from_parts(
+            pt![2., 0., -1.],
+            Quaternion::from_axis_angle(pt![0., 1., 0.], PI / 4.),
+            pt![2., 2., 2.]
+        );
+        assert_same(clip.sample(1.5), half_turn);
+
+        let (translation, _, scale) = clip.sample(2.25).decompose();
+        assert!((translation.x - 3.).abs() < 1e-9);
+        assert!((translation.y - 1.5).abs() < 1e-9);
+        assert!((scale.x - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sample_looping_wraps_around() {
+        let clip = clip();
+        assert_same(clip.sample_looping(3.5), clip.sample(1.5));
+        assert_same(clip.sample_looping(-0.5), clip.sample(1.5));
+        assert_same(clip.sample_looping(1.), clip.sample(1.));
+
+        let identity = Transform::identity();
+        let still = AnimationClip::new(vec![Keyframe::new(0., identity)]);
+        assert_same(still.sample_looping(7.), identity);
+        assert_same(AnimationClip::new(Vec::new()).sample(1.), identity);
+    }
+}
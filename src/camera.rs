@@ -0,0 +1,117 @@
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+use std::collections::HashSet;
+use std::f64;
+
+use types::*;
+use utils::clamp;
+
+
+const MAX_PITCH: f64 = f64::consts::FRAC_PI_2 - 0.01;
+
+// A free-flying first-person camera. WASD moves along the view direction and
+// dragging with the left mouse button turns.
+pub struct FlyCamera {
+    pub position: Point,
+    pub yaw: f64,
+    pub pitch: f64,
+    pub speed: f64,       // World units per second.
+    pub sensitivity: f64, // Radians per pixel of mouse movement.
+}
+
+impl FlyCamera {
+    pub fn new(position: Point) -> FlyCamera {
+        FlyCamera {
+            position: position,
+            yaw: 0.,
+            pitch: 0.,
+            speed: 10.,
+            sensitivity: 0.005,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::MouseMotion { mousestate, xrel, yrel, .. } = *event {
+            if !mousestate.left() { return }
+            self.yaw   -= xrel as f64 * self.sensitivity;
+            self.pitch += yrel as f64 * self.sensitivity;
+            self.pitch = clamp(self.pitch, -MAX_PITCH, MAX_PITCH);
+        }
+    }
+
+    // Moves the camera for `dt` seconds with the given keys held.
+    pub fn update(&mut self, dt: f64, keys: &HashSet<Keycode>) {
+        let rotation = self.rotation();
+        let forward = pt![0., 0., -1.] * rotation;
+        let right   = pt![1., 0.,  0.] * rotation;
+
+        let mut dir = pt![0., 0., 0.];
+        if keys.contains(&Keycode::W) { dir = dir + forward }
+        if keys.contains(&Keycode::S) { dir = dir - forward }
+        if keys.contains(&Keycode::D) { dir = dir + right }
+        if keys.contains(&Keycode::A) { dir = dir - right }
+        if dir.magnitude() == 0. { return }
+
+        self.position = self.position + dir.normalized() * (self.speed * dt);
+    }
+
+    // The view transform, taking world coordinates to camera coordinates
+    // with the camera looking down -z.
+    pub fn to_transform(&self) -> Transform {
+        Transform::rotate_x(-self.pitch)
+            * Transform::rotate_y(-self.yaw)
+            * Transform::translate(-self.position)
+    }
+
+    // Orientation of the camera in the world.
+    fn rotation(&self) -> Transform {
+        Transform::rotate_y(self.yaw) * Transform::rotate_x(self.pitch)
+    }
+}
+
+
+const ORBIT_SENSITIVITY: f64 = 0.01; // Radians per pixel of drag.
+const ZOOM_PER_STEP: f64 = 0.1;      // Fraction of the radius per scroll step.
+const MIN_RADIUS: f64 = 0.1;
+
+// A model-viewer camera circling `target` at a distance of `radius`.
+pub struct OrbitCamera {
+    pub target: Point,
+    pub radius: f64,
+    pub yaw: f64,
+    pub pitch: f64,
+}
+
+impl OrbitCamera {
+    pub fn new(target: Point, radius: f64) -> OrbitCamera {
+        OrbitCamera {
+            target: target,
+            radius: radius,
+            yaw: 0.,
+            pitch: 0.,
+        }
+    }
+
+    // Rotates around the target by a mouse drag of (dx, dy) pixels.
+    pub fn handle_mouse_drag(&mut self, dx: f64, dy: f64) {
+        self.yaw   -= dx * ORBIT_SENSITIVITY;
+        self.pitch += dy * ORBIT_SENSITIVITY;
+        self.pitch = clamp(self.pitch, -MAX_PITCH, MAX_PITCH);
+    }
+
+    // Zooms in for positive `delta` (scroll steps) and out for negative.
+    pub fn handle_scroll(&mut self, delta: f64) {
+        self.radius = (self.radius * (1. - delta * ZOOM_PER_STEP))
+            .max(MIN_RADIUS);
+    }
+
+    // The view transform, taking world coordinates to camera coordinates
+    // with the camera looking down -z at the target.
+    pub fn to_transform(&self) -> Transform {
+        Transform::translate(pt![0., 0., -self.radius])
+            * Transform::rotate_x(-self.pitch)
+            * Transform::rotate_y(-self.yaw)
+            * Transform::translate(-self.target)
+    }
+}
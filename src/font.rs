@@ -0,0 +1,132 @@
+use types::*;
+
+
+pub const GLYPH_SIZE: Dimension = 8;
+
+// An 8x8 monospace font covering printable ASCII (' ' through '~'). Each glyph
+// is a u64 holding one byte per row, top row in the lowest byte, with bit 0 of
+// each byte as the leftmost pixel.
+#[derive(Clone)]
+pub struct BitmapFont {
+    glyphs: [u64; 95],
+}
+
+impl BitmapFont {
+    pub fn new(glyphs: [u64; 95]) -> BitmapFont {
+        BitmapFont { glyphs: glyphs }
+    }
+
+    // The classic IBM PC 8x8 glyph set.
+    pub fn builtin() -> BitmapFont {
+        BitmapFont::new(FONT_8X8)
+    }
+
+    // Characters outside printable ASCII are drawn as '?'.
+    pub fn glyph(&self, c: char) -> u64 {
+        let index =
+            if (' ' ..= '~').contains(&c) { c as usize - 32 } else { 31 };
+        self.glyphs[index]
+    }
+
+    pub fn glyph_pixel(glyph: u64, x: Dimension, y: Dimension) -> bool {
+        (glyph >> (y * GLYPH_SIZE + x)) & 1 == 1
+    }
+}
+
+const FONT_8X8: [u64; 95] = [
+    0x0000000000000000, // ' '
+    0x00180018183C3C18, // '!'
+    0x0000000000003636, // '"'
+    0x0036367F367F3636, // '#'
+    0x000C1F301E033E0C, // '$'
+    0x0063660C18336300, // '%'
+    0x006E333B6E1C361C, // '&'
+    0x0000000000030606, // "'"
+    0x00180C0606060C18, // '('
+    0x00060C1818180C06, // ')'
+    0x0000663CFF3C6600, // '*'
+    0x00000C0C3F0C0C00, // '+'
+    0x060C0C0000000000, // ','
+    0x000000003F000000, // '-'
+    0x000C0C0000000000, // '.'
+    0x000103060C183060, // '/'
+    0x003E676F7B73633E, // '0'
+    0x003F0C0C0C0C0E0C, // '1'
+    0x003F33061C30331E, // '2'
+    0x001E33301C30331E, // '3'
+    0x0078307F33363C38, // '4'
+    0x001E3330301F033F, // '5'
+    0x001E33331F03061C, // '6'
+    0x000C0C0C1830333F, // '7'
+    0x001E33331E33331E, // '8'
+    0x000E18303E33331E, // '9'
+    0x000C0C00000C0C00, // ':'
+    0x060C0C00000C0C00, // ';'
+    0x00180C0603060C18, // '<'
+    0x00003F00003F0000, // '='
+    0x00060C1830180C06, // '>'
+    0x000C000C1830331E, // '?'
+    0x001E037B7B7B633E, // '@'
+    0x0033333F33331E0C, // 'A'
+    0x003F66663E66663F, // 'B'
+    0x003C66030303663C, // 'C'
+    0x001F36666666361F, // 'D'
+    0x007F46161E16467F, // 'E'
+    0x000F06161E16467F, // 'F'
+    0x007C66730303663C, // 'G'
+    0x003333333F333333, // 'H'
+    0x001E0C0C0C0C0C1E, // 'I'
+    0x001E333330303078, // 'J'
+    0x006766361E366667, // 'K'
+    0x007F66460606060F, // 'L'
+    0x0063636B7F7F7763, // 'M'
+    0x006363737B6F6763, // 'N'
+    0x001C36636363361C, // 'O'
+    0x000F06063E66663F, // 'P'
+    0x00381E3B3333331E, // 'Q'
+    0x006766363E66663F, // 'R'
+    0x001E33380E07331E, // 'S'
+    0x001E0C0C0C0C2D3F, // 'T'
+    0x003F333333333333, // 'U'
+    0x000C1E3333333333, // 'V'
+    0x0063777F6B636363, // 'W'
+    0x0063361C1C366363, // 'X'
+    0x001E0C0C1E333333, // 'Y'
+    0x007F664C1831637F, // 'Z'
+    0x001E06060606061E, // '['
+    0x00406030180C0603, // '\\'
+    0x001E18181818181E, // ']'
+    0x0000000063361C08, // '^'
+    0xFF00000000000000, // '_'
+    0x0000000000180C0C, // '`'
+    0x006E333E301E0000, // 'a'
+    0x003B66663E060607, // 'b'
+    0x001E3303331E0000, // 'c'
+    0x006E33333E303038, // 'd'
+    0x001E033F331E0000, // 'e'
+    0x000F06060F06361C, // 'f'
+    0x1F303E33336E0000, // 'g'
+    0x006766666E360607, // 'h'
+    0x001E0C0C0C0E000C, // 'i'
+    0x1E33333030300030, // 'j'
+    0x0067361E36660607, // 'k'
+    0x001E0C0C0C0C0C0E, // 'l'
+    0x00636B7F7F330000, // 'm'
+    0x00333333331F0000, // 'n'
+    0x001E3333331E0000, // 'o'
+    0x0F063E66663B0000, // 'p'
+    0x78303E33336E0000, // 'q'
+    0x000F06666E3B0000, // 'r'
+    0x001F301E033E0000, // 's'
+    0x00182C0C0C3E0C08, // 't'
+    0x006E333333330000, // 'u'
+    0x000C1E3333330000, // 'v'
+    0x00367F7F6B630000, // 'w'
+    0x0063361C36630000, // 'x'
+    0x1F303E3333330000, // 'y'
+    0x003F260C193F0000, // 'z'
+    0x00380C0C070C0C38, // '{'
+    0x0018181800181818, // '|'
+    0x00070C0C380C0C07, // '}'
+    0x0000000000003B6E, // '~'
+];
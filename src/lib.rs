@@ -1,33 +1,40 @@
+#[cfg(feature = "ssao")] extern crate rand;
 extern crate sdl2;
 
 #[macro_use] pub mod types;
+pub mod animation;
+pub mod camera;
+pub mod font;
+pub mod mesh;
 pub mod object;
 pub mod pixel;
+pub mod postprocess;
 pub mod renderer;
+pub mod scene;
 pub mod screen;
+pub mod texture;
+pub mod timing;
 
 use sdl2::event::Event as SdlEvent;
+use sdl2::event::WindowEvent;
 
 use std::error;
-use std::thread;
-use std::time::Duration;
-use std::time::Instant;
 
-mod texture;
 mod utils;
 
 use renderer::Renderer;
 use screen::GraphicalScreen;
+use timing::FrameLimiter;
+use timing::duration_secs;
 
 
-const NANOS_PER_SECOND: u32 = 1_000_000_000;
-
 type InitFunc<WorldState, S> =
     fn (&mut Renderer<S>) -> Result<WorldState, Box<error::Error>>;
 
 type ParseEventFunc<WorldState> =
     fn (&mut LoopState, &mut WorldState, SdlEvent);
-type UpdateFunc<WorldState> = fn (&mut WorldState) -> bool;
+// Takes the length of the previous frame in seconds.
+type UpdateFunc<WorldState> = fn (&mut WorldState, f64) -> bool;
 type RenderFunc<WorldState, S> =
     fn (&mut Renderer<S>, &WorldState) -> Result<(), Box<error::Error>>;
 
@@ -50,36 +57,39 @@ pub fn main_loop<'a, WorldState>
         screen_config.height,
         &sdl_context,
     ));
-    let mut renderer = Renderer::new(screen);
+    let mut renderer = match Renderer::try_new(screen) {
+        Some(renderer) => renderer,
+        None => return Err(From::from("screen too large to render to")),
+    };
     let mut event_pump = sdl_context.event_pump().unwrap();
 
     let mut world_state = try!(init(&mut renderer));
 
     // Main loop.
-    let frame_len_nanos = NANOS_PER_SECOND / screen_config.target_fps;
+    let mut frame_limiter = FrameLimiter::new(screen_config.target_fps);
+    let mut dt = duration_secs(frame_limiter.target_frame_len());
     let mut loop_state = LoopState::new();
     while loop_state.running {
-        // Time frame length.
-        let frame_start = Instant::now();
-
         // Update and render frame.
         for event in event_pump.poll_iter() {
+            if let SdlEvent::Window {
+                win_event: WindowEvent::Resized(w, h), ..
+            } = event {
+                try!(renderer.resize(w as u32, h as u32));
+            }
             parse_event(&mut loop_state, &mut world_state, event);
         }
+        if loop_state.fullscreen != renderer.screen().is_fullscreen() {
+            try!(renderer.screen_mut().set_fullscreen(loop_state.fullscreen));
+        }
         if loop_state.should_tick() {
             loop_state.step = false;
-            let frame_dirty = update(&mut world_state);
+            let frame_dirty = update(&mut world_state, dt);
             if  frame_dirty { try!(render(&mut renderer, &world_state)); }
         }
 
         // Sleep until end of frame.
-        let frame_len = Instant::now() - frame_start;
-        let target_frame_len = Duration::new(0, frame_len_nanos);
-        if frame_len < target_frame_len {
-            thread::sleep(target_frame_len - frame_len);
-        } else {
-            println!("slowed down!");
-        }
+        dt = duration_secs(frame_limiter.tick());
     }
 
     Ok(())
@@ -96,6 +106,7 @@ pub struct LoopState {
     pub running: bool,
     pub paused: bool,
     pub step: bool,
+    pub fullscreen: bool,
 }
 
 impl LoopState {
@@ -104,6 +115,7 @@ impl LoopState {
             running: true,
             paused: false,
             step:   false,
+            fullscreen: false,
         }
     }
 
@@ -4,6 +4,10 @@ extern crate sdl2;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 
+use rusterize::animation::AnimationClip;
+use rusterize::animation::Keyframe;
+use rusterize::camera::FlyCamera;
+use rusterize::camera::OrbitCamera;
 use rusterize::object::Object;
 use rusterize::renderer::LightingMode;
 use rusterize::renderer::Renderer;
@@ -11,6 +15,7 @@ use rusterize::screen::Screen;
 use rusterize::types::*;
 
 use std::cmp::min;
+use std::collections::HashSet;
 use std::error;
 use std::f64;
 use std::process;
@@ -19,11 +24,16 @@ use std::process;
 pub const SCREEN_WIDTH:  u32 = 800;
 pub const SCREEN_HEIGHT: u32 = 600;
 pub const TARGET_FPS:    u32 = 60;
-pub const TIME_PER_TICK: f64 = 1. / (TARGET_FPS as f64);
 
 struct WorldState {
     time: f64,
-    objects: Vec<Object>
+    objects: Vec<Object>,
+    spinner: Object, // Placed by spin_clip.
+    spin_clip: AnimationClip,
+    camera: FlyCamera,
+    orbit_camera: OrbitCamera,
+    orbiting: bool, // Whether the orbit camera is active.
+    keys_down: HashSet<Keycode>,
 }
 
 
@@ -80,29 +90,74 @@ fn init<S: Screen>(renderer: &mut Renderer<S>)
         objects
     };
 
+    // A small cube to the right of the big one, spinning a quarter turn per
+    // second.
+    let spinner = Object::from_file("res/cube.obj")?;
+    let spin_clip = AnimationClip::new(
+        (0 .. 5)
+            .map(|i| {
+                let angle = i as f64 * f64::consts::FRAC_PI_2;
+                let axis = pt![0., 1., 0.];
+                Keyframe::new(i as f64, Transform::from_parts(
+                    pt![8., 0., -20.],
+                    Quaternion::from_axis_angle(axis, angle),
+                    pt![1., 1., 1.]
+                ))
+            })
+            .collect()
+    );
+
     Ok(
         WorldState {
             time: 0.,
             objects: objects,
+            spinner: spinner,
+            spin_clip: spin_clip,
+            camera: FlyCamera::new(pt![0., 0., 0.]),
+            orbit_camera: OrbitCamera::new(pt![0., 0., -20.], 20.),
+            orbiting: false,
+            keys_down: HashSet::new(),
         }
     )
 }
 
 fn parse_event(
     loop_state: &mut rusterize::LoopState,
-    _: &mut WorldState,
+    world_state: &mut WorldState,
     event: Event
 ) {
+    if !world_state.orbiting { world_state.camera.handle_event(&event) }
+
     match event {
         Event::Quit { .. } |
         Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
             loop_state.running = false;
         },
 
+        Event::MouseMotion { mousestate, xrel, yrel, .. }
+            if world_state.orbiting && mousestate.left() =>
+        {
+            world_state.orbit_camera
+                .handle_mouse_drag(xrel as f64, yrel as f64);
+        },
+
+        Event::MouseWheel { y, .. } if world_state.orbiting => {
+            world_state.orbit_camera.handle_scroll(y as f64);
+        },
+
+        Event::KeyUp { keycode: Some(code), .. } => {
+            world_state.keys_down.remove(&code);
+        },
+
         Event::KeyDown { keycode: Some(code), .. } => {
+            world_state.keys_down.insert(code);
             match code {
                 Keycode::P      => loop_state.paused = !loop_state.paused,
                 Keycode::Space  => loop_state.step   = true,
+                Keycode::C      =>
+                    world_state.orbiting = !world_state.orbiting,
+                Keycode::F      =>
+                    loop_state.fullscreen = !loop_state.fullscreen,
                 _ => {}
             }
         },
@@ -112,10 +167,13 @@ fn parse_event(
 }
 
 // Returns true if the frame is made dirty, else false.
-fn update(world_state: &mut WorldState) -> bool {
-    world_state.time += TIME_PER_TICK;
-    world_state.objects[0].rotate_y(TIME_PER_TICK);
-    world_state.objects[0].rotate_x(TIME_PER_TICK);
+fn update(world_state: &mut WorldState, dt: f64) -> bool {
+    world_state.time += dt;
+    world_state.objects[0].rotate_y(dt);
+    world_state.objects[0].rotate_x(dt);
+    if !world_state.orbiting {
+        world_state.camera.update(dt, &world_state.keys_down);
+    }
 
     true // frame dirty
 }
@@ -127,9 +185,16 @@ fn render<T: Screen>(
     -> Result<(), Box<error::Error>>
 {
     renderer.clear();
+    let view = if world_state.orbiting {
+        world_state.orbit_camera.to_transform()
+    } else {
+        world_state.camera.to_transform()
+    };
     for object in &world_state.objects {
-        object.render(renderer);
+        object.render_with_transform(renderer, view);
     }
+    let spin = world_state.spin_clip.sample_looping(world_state.time);
+    world_state.spinner.render_with_transform(renderer, view * spin);
     try!(renderer.display());
     Ok(())
 }
@@ -0,0 +1,204 @@
+pub mod ply;
+pub mod primitives;
+pub mod stl;
+
+use std::cmp::Ordering::Equal;
+
+use types::*;
+
+
+#[derive(Clone)]
+pub struct Mesh {
+    pub triangles: Vec<Triangle>,
+    pub normals: Option<Vec<Point>>, // One per triangle.
+}
+
+impl Mesh {
+    pub fn new(triangles: Vec<Triangle>) -> Mesh {
+        Mesh {
+            triangles: triangles,
+            normals: None,
+        }
+    }
+
+    pub fn with_normals(triangles: Vec<Triangle>, normals: Vec<Point>)
+        -> Mesh
+    {
+        assert!(triangles.len() == normals.len());
+        Mesh {
+            triangles: triangles,
+            normals: Some(normals),
+        }
+    }
+
+    // The smallest box containing every vertex, or None with no triangles.
+    pub fn aabb(&self) -> Option<Aabb> {
+        let points: Vec<Point> = self.triangles.iter()
+            .flat_map(|t| t.to_arr().to_vec())
+            .collect();
+        Aabb::from_points(&points)
+    }
+}
+
+// Applies midpoint subdivision `levels` times, multiplying the triangle count
+// by 4 each time.
+pub fn subdivide_mesh(triangles: &[Triangle], levels: usize) -> Vec<Triangle> {
+    let mut out = triangles.to_vec();
+    for _ in 0 .. levels {
+        out = out.iter()
+            .flat_map(|t| t.subdivide().to_vec())
+            .collect();
+    }
+    out
+}
+
+// The corners of the smallest convex polygon around `points`, by Graham scan,
+// counterclockwise with y up from the lowest point. Collinear points along an
+// edge are left out, as are points with NaN or infinite coordinates.
+pub fn convex_hull_2d(points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut points: Vec<(f64, f64)> = points.iter()
+        .cloned()
+        .filter(|&(x, y)| x.is_finite() && y.is_finite())
+        .collect();
+    let pivot = match points.iter().cloned().min_by(|a, b| {
+        (a.1, a.0).partial_cmp(&(b.1, b.0)).unwrap_or(Equal)
+    }) {
+        Some(pivot) => pivot,
+        None => return Vec::new(),
+    };
+
+    // Turning left from o through a to b is positive.
+    let cross = |o: (f64, f64), a: (f64, f64), b: (f64, f64)| {
+        (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+    };
+    let dist2 = |p: (f64, f64)| {
+        (p.0 - pivot.0).powi(2) + (p.1 - pivot.1).powi(2)
+    };
+    points.sort_by(|&a, &b| {
+        let angle_a = (a.1 - pivot.1).atan2(a.0 - pivot.0);
+        let angle_b = (b.1 - pivot.1).atan2(b.0 - pivot.0);
+        angle_a.partial_cmp(&angle_b)
+            .unwrap_or(Equal)
+            .then(dist2(a).partial_cmp(&dist2(b)).unwrap_or(Equal))
+    });
+
+    let mut hull: Vec<(f64, f64)> = vec![pivot];
+    for p in points {
+        while hull.len() >= 2
+            && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.
+        {
+            hull.pop();
+        }
+        if hull.len() == 1 && p == pivot { continue }
+        hull.push(p);
+    }
+    hull
+}
+
+
+// Versions of one mesh in decreasing detail, each paired with the distance
+// up to which it is used.
+#[derive(Clone)]
+pub struct LODMesh {
+    pub lods: Vec<(f64, Mesh)>,
+}
+
+impl LODMesh {
+    // Sorts `lods` by distance.
+    pub fn new(mut lods: Vec<(f64, Mesh)>) -> LODMesh {
+        lods.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Equal));
+        LODMesh { lods: lods }
+    }
+
+    // The center of the bounding box of the most detailed version.
+    pub fn center(&self) -> Option<Point> {
+        self.lods.first()
+            .and_then(|lod| lod.1.aabb())
+            .map(|aabb| aabb.center())
+    }
+
+    // The first version whose distance reaches `distance`, or the least
+    // detailed one past them all.
+    pub fn select(&self, distance: f64) -> Option<&Mesh> {
+        self.lods.iter()
+            .find(|&&(max_distance, _)| distance <= max_distance)
+            .or_else(|| self.lods.last())
+            .map(|lod| &lod.1)
+    }
+}
+
+
+// Vertices shared between the triangles of an `IndexBuffer`, optionally with
+// one normal per vertex for smooth shading.
+#[derive(Clone)]
+pub struct VertexBuffer {
+    pub vertices: Vec<Point>,
+    pub normals: Option<Vec<Point>>,
+}
+
+impl VertexBuffer {
+    pub fn new(vertices: Vec<Point>) -> VertexBuffer {
+        VertexBuffer {
+            vertices: vertices,
+            normals: None,
+        }
+    }
+
+    pub fn with_normals(vertices: Vec<Point>, normals: Vec<Point>)
+        -> VertexBuffer
+    {
+        assert!(vertices.len() == normals.len());
+        VertexBuffer {
+            vertices: vertices,
+            normals: Some(normals),
+        }
+    }
+}
+
+
+// Triangles as triples of indices into a `VertexBuffer`.
+#[derive(Clone)]
+pub struct IndexBuffer {
+    pub indices: Vec<(usize, usize, usize)>,
+}
+
+impl IndexBuffer {
+    pub fn new(indices: Vec<(usize, usize, usize)>) -> IndexBuffer {
+        IndexBuffer { indices: indices }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subdivide_mesh_levels() {
+        let tris = vec![
+            trigon![pt![0., 0., 0.], pt![1., 0., 0.], pt![0., 1., 0.]],
+            trigon![pt![1., 0., 0.], pt![1., 1., 0.], pt![0., 1., 0.]],
+        ];
+        assert_eq!(subdivide_mesh(&tris, 0).len(), 2);
+        assert_eq!(subdivide_mesh(&tris, 1).len(), 8);
+        let twice = subdivide_mesh(&tris, 2);
+        assert_eq!(twice.len(), 32);
+        let area: f64 = twice.iter().map(|t| t.area()).sum();
+        assert!((area - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn lod_select_by_distance() {
+        let lod = LODMesh::new(vec![
+            (50., Mesh::new(primitives::sphere(1., 4, 6))),
+            (10., Mesh::new(primitives::sphere(1., 16, 24))),
+        ]);
+        let count = |d: f64| lod.select(d).unwrap().triangles.len();
+        // Sorted, so the nearest threshold comes first.
+        assert_eq!(lod.lods[0].0, 10.);
+        assert!(count(5.) > count(30.));
+        assert_eq!(count(10.), count(0.));
+        assert_eq!(count(1e9), count(30.));
+        assert!(LODMesh::new(Vec::new()).select(1.).is_none());
+    }
+}
@@ -0,0 +1,197 @@
+use std::error;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::path::Path;
+
+use types::*;
+
+
+// Loads an ASCII PLY file. Vertices need `x`, `y` and `z` properties (others
+// are ignored); faces with more than three vertices are split into fans.
+pub fn load_ply(path: &Path) -> Result<Vec<Triangle>, Box<error::Error>> {
+    let f = fs::File::open(path)?;
+    parse_ply(io::BufReader::new(f))
+}
+
+pub fn parse_ply<R: BufRead>(reader: R)
+    -> Result<Vec<Triangle>, Box<error::Error>>
+{
+    let mut lines = reader.lines();
+    let mut next_line = || -> Result<String, Box<error::Error>> {
+        match lines.next() {
+            Some(line) => Ok(line?),
+            None => Err(From::from("unexpected end of PLY file")),
+        }
+    };
+
+    // Parse the header.
+    if next_line()?.trim() != "ply" {
+        return Err(From::from("missing PLY magic number"));
+    }
+
+    let mut num_vertices = 0;
+    let mut num_faces = 0;
+    let mut vertex_props: Vec<String> = Vec::new();
+    let mut current_element = String::new();
+    loop {
+        let line = next_line()?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first() {
+            Some(&"format") if words.get(1) != Some(&"ascii") => {
+                return Err(From::from(format!(
+                    "unsupported PLY format: {}",
+                    words[1 ..].join(" ")
+                )));
+            },
+            Some(&"element") => {
+                if words.len() != 3 {
+                    return Err(From::from(format!(
+                        "bad element line: {}",
+                        line
+                    )));
+                }
+                let count = words[2].parse::<usize>()?;
+                match words[1] {
+                    "vertex" => num_vertices = count,
+                    "face"   => num_faces    = count,
+                    other => return Err(From::from(format!(
+                        "unsupported PLY element: {}",
+                        other
+                    ))),
+                }
+                current_element = words[1].to_string();
+            },
+            Some(&"property") if current_element == "vertex" => {
+                vertex_props.push(words[words.len() - 1].to_string());
+            },
+            Some(&"end_header") => break,
+            _ => {}, // Comments, obj_info and blank lines.
+        }
+    }
+
+    let prop_index = |name: &str| -> Result<usize, Box<error::Error>> {
+        vertex_props.iter()
+            .position(|p| p == name)
+            .ok_or_else(|| From::from(format!(
+                "vertex has no {} property",
+                name
+            )))
+    };
+    let ix = prop_index("x")?;
+    let iy = prop_index("y")?;
+    let iz = prop_index("z")?;
+
+    // Parse the body. The counts come from the file, so only reserve up to
+    // a sane amount up front and let the vectors grow past that.
+    let mut vertices = Vec::with_capacity(num_vertices.min(1 << 16));
+    while vertices.len() < num_vertices {
+        let line = next_line()?;
+        if line.trim().len() == 0 { continue }
+        let values = line.split_whitespace()
+            .map(|s| s.parse::<Coord>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.len() < vertex_props.len() {
+            return Err(From::from(format!("short vertex line: {}", line)));
+        }
+        vertices.push(pt![values[ix], values[iy], values[iz]]);
+    }
+
+    let mut tris = Vec::with_capacity(num_faces.min(1 << 16));
+    let mut faces_read = 0;
+    while faces_read < num_faces {
+        let line = next_line()?;
+        if line.trim().len() == 0 { continue }
+        let values = line.split_whitespace()
+            .map(|s| s.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()?;
+        let count = values[0];
+        if count < 3 || values.len() - 1 < count {
+            return Err(From::from(format!("bad face line: {}", line)));
+        }
+
+        let mut face = Vec::with_capacity(count);
+        for &i in &values[1 .. count + 1] {
+            match vertices.get(i) {
+                Some(&p) => face.push(p),
+                None => return Err(From::from(format!(
+                    "face references missing vertex {}",
+                    i
+                ))),
+            }
+        }
+        for i in 1 .. count - 1 {
+            tris.push(trigon![face[0], face[i], face[i + 1]]);
+        }
+        faces_read += 1;
+    }
+
+    Ok(tris)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYRAMID: &str = "ply
+format ascii 1.0
+comment a square pyramid
+element vertex 5
+property float x
+property float y
+property float z
+property uchar red
+element face 2
+property list uchar int vertex_indices
+end_header
+0 0 0 255
+1 0 0 255
+1 1 0 255
+0 1 0 255
+
+0.5 0.5 2.5 255
+4 0 1 2 3
+3 0 1 4
+";
+
+    fn coords(p: Point) -> (Coord, Coord, Coord) { (p.x, p.y, p.z) }
+
+    #[test]
+    fn parses_triangles_and_fans() {
+        let tris = parse_ply(PYRAMID.as_bytes()).unwrap();
+        assert_eq!(tris.len(), 3);
+
+        // The quad is split into a fan around its first vertex.
+        let corners: Vec<_> = tris.iter()
+            .map(|t| (coords(t.p1), coords(t.p2), coords(t.p3)))
+            .collect();
+        assert_eq!(corners[0], ((0., 0., 0.), (1., 0., 0.), (1., 1., 0.)));
+        assert_eq!(corners[1], ((0., 0., 0.), (1., 1., 0.), (0., 1., 0.)));
+        assert_eq!(corners[2], ((0., 0., 0.), (1., 0., 0.), (0.5, 0.5, 2.5)));
+    }
+
+    #[test]
+    fn rejects_bad_files() {
+        let binary = PYRAMID.replace("ascii", "binary_little_endian");
+        assert!(parse_ply(binary.as_bytes()).is_err());
+        assert!(parse_ply(&b"obj\n"[..]).is_err());
+
+        let missing = PYRAMID.replace("3 0 1 4", "3 0 1 5");
+        assert!(parse_ply(missing.as_bytes()).is_err());
+        let truncated = PYRAMID.replace("3 0 1 4\n", "");
+        assert!(parse_ply(truncated.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rejects_huge_counts() {
+        let vertices = PYRAMID.replace("vertex 5", "vertex 99999999999999");
+        assert!(parse_ply(vertices.as_bytes()).is_err());
+        let faces = PYRAMID.replace("face 2", "face 99999999999999");
+        assert!(parse_ply(faces.as_bytes()).is_err());
+
+        let face = format!("{} 0 1 4", usize::MAX);
+        let huge = PYRAMID.replace("3 0 1 4", &face);
+        assert!(parse_ply(huge.as_bytes()).is_err());
+    }
+}
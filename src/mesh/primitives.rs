@@ -0,0 +1,213 @@
+use std::f64;
+
+use types::*;
+
+
+// Triangles are wound counterclockwise seen from outside, so their normals
+// point outwards.
+
+pub fn cube(half_size: f64) -> Vec<Triangle> {
+    let s = half_size;
+    let vertices = [
+        pt![-s, -s, -s], pt![ s, -s, -s], pt![ s,  s, -s], pt![-s,  s, -s],
+        pt![-s, -s,  s], pt![ s, -s,  s], pt![ s,  s,  s], pt![-s,  s,  s],
+    ];
+    // Each face as a quad, counterclockwise from outside.
+    let faces = [
+        (4, 5, 6, 7), // +z
+        (1, 0, 3, 2), // -z
+        (5, 1, 2, 6), // +x
+        (0, 4, 7, 3), // -x
+        (7, 6, 2, 3), // +y
+        (0, 1, 5, 4), // -y
+    ];
+
+    let mut indices = Vec::with_capacity(2 * faces.len());
+    for &quad in &faces { push_quad(&mut indices, quad) }
+    expand(&vertices, &indices)
+}
+
+// A UV sphere around the origin with poles on the y axis. `rings` bands run
+// from pole to pole and `sectors` slices run around the axis.
+pub fn sphere(radius: f64, rings: usize, sectors: usize) -> Vec<Triangle> {
+    let (vertices, indices) = sphere_indexed(radius, rings, sectors);
+    expand(&vertices, &indices)
+}
+
+// A closed cylinder around the y axis, centered on the origin.
+pub fn cylinder(radius: f64, height: f64, segments: usize) -> Vec<Triangle> {
+    let segments = segments.max(3);
+    let hh = height / 2.;
+
+    // Bottom ring, top ring, then the two cap centers.
+    let mut vertices = Vec::with_capacity(2 * segments + 2);
+    for &y in &[-hh, hh] {
+        for j in 0 .. segments {
+            let theta = 2. * f64::consts::PI * j as f64 / segments as f64;
+            vertices.push(pt![radius * theta.cos(), y, -radius * theta.sin()]);
+        }
+    }
+    let bottom_center = vertices.len();
+    vertices.push(pt![0., -hh, 0.]);
+    let top_center = vertices.len();
+    vertices.push(pt![0., hh, 0.]);
+
+    let mut indices = Vec::with_capacity(4 * segments);
+    for j in 0 .. segments {
+        let next = (j + 1) % segments;
+        push_quad(&mut indices, (j, next, segments + next, segments + j));
+        indices.push((bottom_center, next, j));
+        indices.push((top_center, segments + j, segments + next));
+    }
+    expand(&vertices, &indices)
+}
+
+// The latitude and longitude lines of `sphere` as line segments.
+pub fn sphere_wireframe(radius: f64, rings: usize, sectors: usize)
+    -> Vec<(Point, Point)>
+{
+    let rings = rings.max(2);
+    let sectors = sectors.max(3);
+    let vertices = sphere_vertices(radius, rings, sectors);
+
+    let row = sectors + 1;
+    let mut edges = Vec::with_capacity(2 * rings * sectors);
+    for i in 0 .. rings + 1 {
+        for j in 0 .. sectors {
+            let v = i * row + j;
+            // Latitude lines, skipping the poles.
+            if i != 0 && i != rings {
+                edges.push((vertices[v], vertices[v + 1]));
+            }
+            // Longitude lines.
+            if i != rings {
+                edges.push((vertices[v], vertices[v + row]));
+            }
+        }
+    }
+    edges
+}
+
+fn sphere_indexed(radius: f64, rings: usize, sectors: usize)
+    -> (Vec<Point>, Vec<(usize, usize, usize)>)
+{
+    let rings = rings.max(2);
+    let sectors = sectors.max(3);
+    let vertices = sphere_vertices(radius, rings, sectors);
+
+    // The triangles touching a pole would be degenerate, so the first and
+    // last bands get one triangle per sector instead of two.
+    let row = sectors + 1;
+    let mut indices = Vec::with_capacity(2 * sectors * (rings - 1));
+    for i in 0 .. rings {
+        for j in 0 .. sectors {
+            let top_left  = i * row + j;
+            let top_right = top_left + 1;
+            let bot_left  = top_left + row;
+            let bot_right = bot_left + 1;
+            if i != 0 { indices.push((top_left, bot_left, top_right)) }
+            if i != rings - 1 {
+                indices.push((top_right, bot_left, bot_right));
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+// (rings + 1) rows of (sectors + 1) vertices from the +y pole to the -y
+// pole, the last column repeating the first.
+fn sphere_vertices(radius: f64, rings: usize, sectors: usize) -> Vec<Point> {
+    let mut vertices = Vec::with_capacity((rings + 1) * (sectors + 1));
+    for i in 0 .. rings + 1 {
+        let phi = f64::consts::PI * i as f64 / rings as f64;
+        for j in 0 .. sectors + 1 {
+            let theta = 2. * f64::consts::PI * j as f64 / sectors as f64;
+            vertices.push(pt![
+                radius * phi.sin() * theta.cos(),
+                radius * phi.cos(),
+                -radius * phi.sin() * theta.sin()
+            ]);
+        }
+    }
+    vertices
+}
+
+// Splits the quad (a, b, c, d), given counterclockwise, into two triangles.
+fn push_quad(
+    indices: &mut Vec<(usize, usize, usize)>,
+    (a, b, c, d): (usize, usize, usize, usize)
+) {
+    indices.push((a, b, c));
+    indices.push((a, c, d));
+}
+
+fn expand(vertices: &[Point], indices: &[(usize, usize, usize)])
+    -> Vec<Triangle>
+{
+    indices.iter()
+        .map(|&(a, b, c)| trigon![vertices[a], vertices[b], vertices[c]])
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn vertices(tris: &[Triangle]) -> Vec<Point> {
+        tris.iter().flat_map(|t| t.to_arr().to_vec()).collect()
+    }
+
+    // Every shape here is convex around the origin, so outward normals
+    // point away from it.
+    fn assert_outward(tris: &[Triangle]) {
+        for &t in tris {
+            let centroid = (t.p1 + t.p2 + t.p3) * (1. / 3.);
+            assert!(t.normal().dot(centroid) > 0.);
+        }
+    }
+
+    #[test]
+    fn cube_surface() {
+        let tris = cube(1.5);
+        assert_eq!(tris.len(), 12);
+        for p in vertices(&tris) {
+            for &c in &[p.x, p.y, p.z] {
+                assert!((c.abs() - 1.5).abs() < EPSILON);
+            }
+        }
+        assert_outward(&tris);
+    }
+
+    #[test]
+    fn sphere_surface() {
+        let tris = sphere(2., 6, 8);
+        // Two per sector in each band but the two around the poles.
+        assert_eq!(tris.len(), 2 * 8 * 5);
+        for p in vertices(&tris) {
+            assert!((p.magnitude() - 2.).abs() < EPSILON);
+        }
+        assert!(tris.iter().all(|t| !t.is_degenerate()));
+        assert_outward(&tris);
+
+        // Too few rings and sectors are raised to the minimum.
+        assert_eq!(sphere(1., 0, 0).len(), 2 * 3);
+    }
+
+    #[test]
+    fn cylinder_surface() {
+        let (r, hh) = (0.5, 2.);
+        let tris = cylinder(r, 2. * hh, 10);
+        assert_eq!(tris.len(), 4 * 10);
+        for p in vertices(&tris) {
+            let dist = (p.x * p.x + p.z * p.z).sqrt();
+            let on_side = (dist - r).abs() < EPSILON;
+            let on_cap = (p.y.abs() - hh).abs() < EPSILON && dist <= r;
+            assert!(on_side || on_cap, "{:?} is off the cylinder", p);
+        }
+        assert_outward(&tris);
+    }
+}
@@ -0,0 +1,214 @@
+use std::error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::str;
+
+use types::*;
+
+
+// Loads an ASCII or binary STL file, discarding the stored facet normals.
+pub fn load_stl(path: &Path) -> Result<Vec<Triangle>, Box<error::Error>> {
+    let mut data = Vec::new();
+    fs::File::open(path)?.read_to_end(&mut data)?;
+    parse_stl(&data)
+}
+
+pub fn parse_stl(data: &[u8]) -> Result<Vec<Triangle>, Box<error::Error>> {
+    Ok(parse_stl_with_normals(data)?
+        .into_iter()
+        .map(|(t, _)| t)
+        .collect())
+}
+
+// Returns each facet along with the normal stored for it in the file.
+pub fn parse_stl_with_normals(data: &[u8])
+    -> Result<Vec<(Triangle, Point)>, Box<error::Error>>
+{
+    // Binary files may also begin with "solid", so only treat the data as
+    // ASCII when its size doesn't match the binary layout.
+    if data.starts_with(b"solid") && !is_binary_size(data) {
+        parse_ascii(str::from_utf8(data)?)
+    } else {
+        parse_binary(data)
+    }
+}
+
+const HEADER_LEN: usize = 80;
+const FACET_LEN:  usize = 50;
+
+fn is_binary_size(data: &[u8]) -> bool {
+    if data.len() < HEADER_LEN + 4 { return false }
+    let num_tris = read_u32(data, HEADER_LEN) as usize;
+    data.len() == HEADER_LEN + 4 + FACET_LEN * num_tris
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    (data[at] as u32)
+        | (data[at + 1] as u32) << 8
+        | (data[at + 2] as u32) << 16
+        | (data[at + 3] as u32) << 24
+}
+
+fn read_point(data: &[u8], at: usize) -> Point {
+    let coord = |i: usize| f32::from_bits(read_u32(data, at + 4 * i)) as Coord;
+    pt![coord(0), coord(1), coord(2)]
+}
+
+fn parse_binary(data: &[u8])
+    -> Result<Vec<(Triangle, Point)>, Box<error::Error>>
+{
+    if data.len() < HEADER_LEN + 4 {
+        return Err(From::from("STL file too short"));
+    }
+    let num_tris = read_u32(data, HEADER_LEN) as usize;
+    if data.len() < HEADER_LEN + 4 + FACET_LEN * num_tris {
+        return Err(From::from(format!(
+            "expected {} facets, file is truncated",
+            num_tris
+        )));
+    }
+
+    // Each facet is a normal and three vertices as little-endian f32 triples,
+    // followed by a two-byte attribute count.
+    let mut facets = Vec::with_capacity(num_tris);
+    for i in 0 .. num_tris {
+        let at = HEADER_LEN + 4 + FACET_LEN * i;
+        facets.push((
+            trigon![
+                read_point(data, at + 12),
+                read_point(data, at + 24),
+                read_point(data, at + 36)
+            ],
+            read_point(data, at)
+        ));
+    }
+    Ok(facets)
+}
+
+fn parse_ascii(text: &str)
+    -> Result<Vec<(Triangle, Point)>, Box<error::Error>>
+{
+    let parse_point = |words: &[&str]| -> Result<Point, Box<error::Error>> {
+        if words.len() != 3 {
+            return Err(From::from(format!(
+                "expected 3 coords, found {}",
+                words.len()
+            )));
+        }
+        Ok(pt![
+            words[0].parse::<Coord>()?,
+            words[1].parse::<Coord>()?,
+            words[2].parse::<Coord>()?
+        ])
+    };
+
+    let mut facets = Vec::new();
+    let mut normal = pt![0., 0., 0.];
+    let mut pts = Vec::with_capacity(3);
+    for line in text.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.first() {
+            Some(&"facet") => {
+                if words.get(1) != Some(&"normal") {
+                    return Err(From::from(format!("bad facet line: {}", line)));
+                }
+                normal = parse_point(&words[2 ..])?;
+                pts.clear();
+            },
+            Some(&"vertex") => pts.push(parse_point(&words[1 ..])?),
+            Some(&"endfacet") => {
+                if pts.len() != 3 {
+                    return Err(From::from(format!(
+                        "expected 3 vertices per facet, found {}",
+                        pts.len()
+                    )));
+                }
+                facets.push((trigon![pts[0], pts[1], pts[2]], normal));
+            },
+            _ => {}, // solid, outer loop, endloop, endsolid.
+        }
+    }
+    Ok(facets)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "solid tri
+  facet normal 0 0 1
+    outer loop
+      vertex 0 0 0
+      vertex 1 0 0
+      vertex 0 1 0
+    endloop
+  endfacet
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 0
+      vertex 0 2 0
+      vertex 2 0 0
+    endloop
+  endfacet
+endsolid tri
+";
+
+    // A binary file with `header` padded to 80 bytes and one facet per
+    // (normal, vertices) pair.
+    fn binary(header: &[u8], facets: &[[[f32; 3]; 4]]) -> Vec<u8> {
+        let mut data = header.to_vec();
+        data.resize(HEADER_LEN, 0);
+        data.extend_from_slice(&(facets.len() as u32).to_le_bytes());
+        for facet in facets {
+            for v in facet.iter() {
+                for c in v.iter() {
+                    data.extend_from_slice(&c.to_bits().to_le_bytes());
+                }
+            }
+            data.extend_from_slice(&[0, 0]);
+        }
+        data
+    }
+
+    fn coords(p: Point) -> (Coord, Coord, Coord) { (p.x, p.y, p.z) }
+
+    #[test]
+    fn parses_ascii() {
+        let facets = parse_stl_with_normals(ASCII.as_bytes()).unwrap();
+        assert_eq!(facets.len(), 2);
+        let (t, n) = facets[1];
+        assert_eq!(coords(n), (0., 0., -1.));
+        assert_eq!(coords(t.p1), (0., 0., 0.));
+        assert_eq!(coords(t.p2), (0., 2., 0.));
+        assert_eq!(coords(t.p3), (2., 0., 0.));
+    }
+
+    #[test]
+    fn parses_binary() {
+        let data = binary(b"made by hand", &[
+            [[0., 0., 1.], [0., 0., 0.], [1., 0., 0.], [0., 1., 0.5]],
+        ]);
+        assert_eq!(data.len(), 134);
+        let facets = parse_stl_with_normals(&data).unwrap();
+        assert_eq!(facets.len(), 1);
+        let (t, n) = facets[0];
+        assert_eq!(coords(n), (0., 0., 1.));
+        assert_eq!(coords(t.p2), (1., 0., 0.));
+        assert_eq!(coords(t.p3), (0., 1., 0.5));
+    }
+
+    #[test]
+    fn binary_files_may_start_with_solid() {
+        let facet = [[0., 0., 1.], [0., 0., 0.], [1., 0., 0.], [0., 1., 0.]];
+        let data = binary(b"solid but binary", &[facet, facet]);
+        assert_eq!(parse_stl(&data).unwrap().len(), 2);
+
+        // A byte short of the binary layout, the same data reads as ASCII
+        // and fails.
+        assert!(parse_stl(&data[.. data.len() - 1]).is_err());
+        let data = binary(b"no solid", &[facet, facet]);
+        assert!(parse_stl(&data[.. data.len() - 1]).is_err());
+    }
+}
@@ -1,5 +1,8 @@
 #![allow(dead_code)]
 
+use utils::clamp;
+
+
 pub const BLACK: Pixel = (0x00, 0x00, 0x00);
 pub const WHITE: Pixel = (0xff, 0xff, 0xff);
 pub const RED:   Pixel = (0xff, 0x00, 0x00);
@@ -18,3 +21,215 @@ pub fn as_char(p: Pixel) -> char {
     else if value > 0x0c0 { '.' }
     else                  { ' ' }
 }
+
+// Luminosity-weighted gray of the same perceived brightness.
+pub fn to_grayscale(p: Pixel) -> Pixel {
+    let (r, g, b) = p;
+    let value = (
+        0.299 * r as f64 +
+        0.587 * g as f64 +
+        0.114 * b as f64
+    ).round() as u8;
+    (value, value, value)
+}
+
+// Linearly interpolates each channel from `a` (t = 0) to `b` (t = 1).
+// Results outside the channel range are clamped.
+pub fn lerp(a: Pixel, b: Pixel, t: f64) -> Pixel {
+    let channel = |x: u8, y: u8| {
+        let v = x as f64 + (y as f64 - x as f64) * t;
+        clamp(v.round(), 0., 255.) as u8
+    };
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+// Builds a color from hue in [0, 360) and saturation, lightness in [0, 1].
+pub fn from_hsl(h: f64, s: f64, l: f64) -> Pixel {
+    let h = ((h % 360.) + 360.) % 360.;
+    let s = clamp(s, 0., 1.);
+    let l = clamp(l, 0., 1.);
+
+    let c = (1. - (2. * l - 1.).abs()) * s;
+    let x = c * (1. - ((h / 60.) % 2. - 1.).abs());
+    let m = l - c / 2.;
+    let (r, g, b) =
+        if      h <  60. { (c,  x,  0.) }
+        else if h < 120. { (x,  c,  0.) }
+        else if h < 180. { (0., c,  x ) }
+        else if h < 240. { (0., x,  c ) }
+        else if h < 300. { (x,  0., c ) }
+        else             { (c,  0., x ) };
+
+    let channel = |v: f64| clamp(((v + m) * 255.).round(), 0., 255.) as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+// Returns (hue, saturation, lightness) with hue in [0, 360) and the others in
+// [0, 1]. Grays have hue and saturation 0.
+pub fn to_hsl(p: Pixel) -> (f64, f64, f64) {
+    let r = p.0 as f64 / 255.;
+    let g = p.1 as f64 / 255.;
+    let b = p.2 as f64 / 255.;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.;
+    let d = max - min;
+    if d == 0. { return (0., 0., l) }
+
+    let s = d / (1. - (2. * l - 1.).abs());
+    let h =
+        if      max == r { 60. * (((g - b) / d) % 6.) }
+        else if max == g { 60. * ((b - r) / d + 2.) }
+        else             { 60. * ((r - g) / d + 4.) };
+    let h = if h < 0. { h + 360. } else { h };
+
+    (h, s, l)
+}
+
+// Adds `b` onto `a`, saturating at white.
+pub fn blend_additive(a: Pixel, b: Pixel) -> Pixel {
+    (a.0.saturating_add(b.0), a.1.saturating_add(b.1), a.2.saturating_add(b.2))
+}
+
+// Multiplies the channels as values in [0, 1], which only darkens.
+pub fn blend_multiply(a: Pixel, b: Pixel) -> Pixel {
+    blend_channels(a, b, |x, y| x * y)
+}
+
+// Inverse of multiplying the inverses, which only lightens.
+pub fn blend_screen(a: Pixel, b: Pixel) -> Pixel {
+    blend_channels(a, b, |x, y| 1. - (1. - x) * (1. - y))
+}
+
+// Multiplies where `a` is dark and screens where it is light.
+pub fn blend_overlay(a: Pixel, b: Pixel) -> Pixel {
+    blend_channels(a, b, |x, y| {
+        if x < 0.5 {
+            2. * x * y
+        } else {
+            1. - 2. * (1. - x) * (1. - y)
+        }
+    })
+}
+
+fn blend_channels<F>(a: Pixel, b: Pixel, f: F) -> Pixel
+    where F: Fn(f64, f64) -> f64
+{
+    let channel = |x: u8, y: u8| {
+        let v = f(x as f64 / 255., y as f64 / 255.);
+        (clamp(v, 0., 1.) * 255.).round() as u8
+    };
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_endpoints_and_middle() {
+        let (a, b) = ((10, 200, 0), (30, 100, 255));
+        assert_eq!(lerp(a, b, 0.), a);
+        assert_eq!(lerp(a, b, 1.), b);
+        assert_eq!(lerp(a, b, 0.5), (20, 150, 128));
+    }
+
+    #[test]
+    fn lerp_clamps_outside_the_channel_range() {
+        let (a, b) = ((100, 200, 50), (200, 100, 250));
+        // Unclamped, these would be (300, 0, 450) and (0, 300, -150).
+        assert_eq!(lerp(a, b, 2.), (255, 0, 255));
+        assert_eq!(lerp(a, b, -1.), (0, 255, 0));
+        assert_eq!(lerp(BLACK, WHITE, 10.), WHITE);
+    }
+
+    #[test]
+    fn grayscale_of_known_colors() {
+        // 0.299 * 255 + 0.587 * 128 + 0.114 * 0 = 151.4
+        let (r, g, b) = to_grayscale((255, 128, 0));
+        assert!((r as i16 - 151).abs() <= 1);
+        assert!(r == g && g == b);
+        assert_eq!(to_grayscale(BLUE), (29, 29, 29));
+        assert_eq!(to_grayscale(WHITE), WHITE);
+    }
+
+    fn assert_hsl(p: Pixel, expected: (f64, f64, f64)) {
+        let (h, s, l) = to_hsl(p);
+        assert!((h - expected.0).abs() < 1e-6, "hue {} of {:?}", h, p);
+        assert!((s - expected.1).abs() < 1e-6, "saturation {} of {:?}", s, p);
+        assert!((l - expected.2).abs() < 1e-6, "lightness {} of {:?}", l, p);
+        assert_eq!(from_hsl(h, s, l), p);
+    }
+
+    #[test]
+    fn hsl_round_trips() {
+        assert_hsl(BLACK, (0., 0., 0.));
+        assert_hsl(WHITE, (0., 0., 1.));
+        assert_hsl(RED, (0., 1., 0.5));
+        assert_hsl((51, 153, 102), (150., 0.5, 0.4));
+    }
+
+    #[test]
+    fn hsl_round_trips_grays_and_colors() {
+        for v in 0 .. 256 {
+            let gray = (v as u8, v as u8, v as u8);
+            let (h, s, l) = to_hsl(gray);
+            assert_eq!(from_hsl(h, s, l), gray);
+        }
+        for &p in &[(255, 128, 0), (12, 34, 56), (200, 10, 250)] {
+            let (h, s, l) = to_hsl(p);
+            assert_eq!(from_hsl(h, s, l), p);
+        }
+        // Hues wrap around.
+        assert_eq!(from_hsl(360., 1., 0.5), RED);
+        assert_eq!(from_hsl(-240., 1., 0.5), GREEN);
+    }
+
+    #[test]
+    fn blend_additive_saturates() {
+        assert_eq!(blend_additive((10, 20, 30), (1, 2, 3)), (11, 22, 33));
+        assert_eq!(blend_additive((200, 100, 255), (100, 155, 1)), WHITE);
+        assert_eq!(blend_additive(WHITE, WHITE), WHITE);
+        assert_eq!(blend_additive(BLACK, (7, 8, 9)), (7, 8, 9));
+    }
+
+    #[test]
+    fn blend_multiply_only_darkens() {
+        assert_eq!(blend_multiply(WHITE, (12, 34, 56)), (12, 34, 56));
+        assert_eq!(blend_multiply(BLACK, WHITE), BLACK);
+        let m = blend_multiply((128, 255, 64), (128, 128, 255));
+        assert_eq!(m, (64, 128, 64));
+        let (a, b) = ((200, 150, 100), (180, 90, 250));
+        let m = blend_multiply(a, b);
+        assert!(m.0 <= a.0.min(b.0));
+        assert!(m.1 <= a.1.min(b.1));
+        assert!(m.2 <= a.2.min(b.2));
+    }
+
+    #[test]
+    fn blend_screen_only_lightens() {
+        assert_eq!(blend_screen(BLACK, (12, 34, 56)), (12, 34, 56));
+        assert_eq!(blend_screen(WHITE, BLACK), WHITE);
+        assert_eq!(blend_screen(WHITE, WHITE), WHITE);
+        assert_eq!(blend_screen((128, 0, 255), (128, 255, 0)), (192, 255, 255));
+        let (a, b) = ((200, 150, 100), (180, 90, 250));
+        let s = blend_screen(a, b);
+        assert!(s.0 >= a.0.max(b.0));
+        assert!(s.1 >= a.1.max(b.1));
+        assert!(s.2 >= a.2.max(b.2));
+    }
+
+    #[test]
+    fn blend_overlay_extremes() {
+        // Multiplies below half and screens above.
+        assert_eq!(blend_overlay(BLACK, WHITE), BLACK);
+        assert_eq!(blend_overlay(WHITE, BLACK), WHITE);
+        assert_eq!(blend_overlay((64, 64, 64), (128, 255, 0)), (64, 128, 0));
+        let o = blend_overlay((192, 192, 192), (128, 255, 0));
+        assert_eq!(o, (192, 255, 129));
+        assert_eq!(blend_overlay(WHITE, WHITE), WHITE);
+        assert_eq!(blend_overlay(BLACK, BLACK), BLACK);
+    }
+}
@@ -0,0 +1,466 @@
+#[cfg(feature = "ssao")] use std::f64;
+
+use pixel;
+use texture::Texture;
+#[cfg(feature = "ssao")] use types::PixCoord;
+use utils::*;
+
+
+// A full-screen pass run on the rendered frame before it is displayed.
+// `dst` has the same dimensions as `src`.
+pub trait PostProcess {
+    fn apply(&self, src: &Texture, dst: &mut Texture);
+}
+
+
+pub struct GrayscaleFilter;
+
+impl PostProcess for GrayscaleFilter {
+    fn apply(&self, src: &Texture, dst: &mut Texture) {
+        for (out, &p) in dst.pixels.iter_mut().zip(src.pixels.iter()) {
+            *out = pixel::to_grayscale(p);
+        }
+    }
+}
+
+
+pub struct InvertFilter;
+
+impl PostProcess for InvertFilter {
+    fn apply(&self, src: &Texture, dst: &mut Texture) {
+        for (out, &(r, g, b)) in dst.pixels.iter_mut().zip(src.pixels.iter()) {
+            *out = (0xff - r, 0xff - g, 0xff - b);
+        }
+    }
+}
+
+
+// Outlines edges by the Sobel gradient of each pixel's luminance. Pixels whose
+// gradient magnitude (normalized to [0, 1]) reaches `threshold` become white,
+// the rest black. A threshold of 0 keeps the raw magnitude as brightness.
+pub struct SobelFilter {
+    pub threshold: f64,
+}
+
+impl SobelFilter {
+    pub fn new(threshold: f64) -> SobelFilter {
+        SobelFilter { threshold: threshold }
+    }
+}
+
+impl PostProcess for SobelFilter {
+    fn apply(&self, src: &Texture, dst: &mut Texture) {
+        const KERNEL_X: [[f64; 3]; 3] = [
+            [-1., 0., 1.],
+            [-2., 0., 2.],
+            [-1., 0., 1.],
+        ];
+        const KERNEL_Y: [[f64; 3]; 3] = [
+            [-1., -2., -1.],
+            [ 0.,  0.,  0.],
+            [ 1.,  2.,  1.],
+        ];
+        // Largest magnitude reachable with luminance in [0, 1].
+        let max_magnitude = 4. * 2f64.sqrt();
+
+        let w = src.w as i64;
+        let h = src.h as i64;
+        let luminance = |x: i64, y: i64| {
+            let x = clamp(x, 0, w - 1);
+            let y = clamp(y, 0, h - 1);
+            let (r, g, b) = src.pixels[(y * w + x) as usize];
+            (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) / 255.
+        };
+
+        for y in 0 .. h {
+            for x in 0 .. w {
+                let mut gx = 0.;
+                let mut gy = 0.;
+                for j in 0 .. 3 {
+                    for i in 0 .. 3 {
+                        let l = luminance(x + i as i64 - 1, y + j as i64 - 1);
+                        gx += KERNEL_X[j][i] * l;
+                        gy += KERNEL_Y[j][i] * l;
+                    }
+                }
+
+                let magnitude = (gx * gx + gy * gy).sqrt() / max_magnitude;
+                let value = if self.threshold > 0. {
+                    if magnitude >= self.threshold { 0xff } else { 0x00 }
+                } else {
+                    (clamp(magnitude, 0., 1.) * 255.).round() as u8
+                };
+                dst.pixels[(y * w + x) as usize] = (value, value, value);
+            }
+        }
+    }
+}
+
+
+// Simulates a CRT by darkening every `line_height`-th row by `intensity`
+// (0 leaves the row as is, 1 blacks it out).
+pub struct ScanlineFilter {
+    pub intensity: f64,
+    pub line_height: usize,
+}
+
+impl ScanlineFilter {
+    pub fn new(intensity: f64, line_height: usize) -> ScanlineFilter {
+        ScanlineFilter {
+            intensity: intensity,
+            line_height: line_height,
+        }
+    }
+}
+
+impl PostProcess for ScanlineFilter {
+    fn apply(&self, src: &Texture, dst: &mut Texture) {
+        let factor = 1. - clamp(self.intensity, 0., 1.);
+        let line_height = self.line_height.max(1);
+        let rows = dst.pixels
+            .chunks_mut(src.w.max(1) as usize)
+            .zip(src.rows());
+        for (y, (out_row, in_row)) in rows.enumerate() {
+            let darken = y % line_height == 0;
+            for (out, &(r, g, b)) in out_row.iter_mut().zip(in_row.iter()) {
+                *out = if darken {
+                    (
+                        (r as f64 * factor) as u8,
+                        (g as f64 * factor) as u8,
+                        (b as f64 * factor) as u8
+                    )
+                } else {
+                    (r, g, b)
+                };
+            }
+        }
+    }
+}
+
+
+// Makes bright areas glow: pixels whose luminance (in [0, 1]) exceeds
+// `threshold` are box-blurred over `radius` pixels and added back on top of
+// the frame, scaled by `intensity`.
+pub struct BloomFilter {
+    pub threshold: f64,
+    pub radius: usize,
+    pub intensity: f64,
+}
+
+impl Default for BloomFilter {
+    fn default() -> BloomFilter {
+        BloomFilter {
+            threshold: 0.8,
+            radius: 4,
+            intensity: 1.,
+        }
+    }
+}
+
+impl BloomFilter {
+    pub fn new() -> BloomFilter {
+        BloomFilter::default()
+    }
+}
+
+impl PostProcess for BloomFilter {
+    fn apply(&self, src: &Texture, dst: &mut Texture) {
+        let w = src.w as usize;
+        let h = src.h as usize;
+
+        // Extract the bright pixels.
+        let bright: Vec<(f32, f32, f32)> = src.pixels.iter()
+            .map(|&(r, g, b)| {
+                let lum =
+                    (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64)
+                    / 255.;
+                if lum > self.threshold {
+                    (r as f32 / 255., g as f32 / 255., b as f32 / 255.)
+                } else {
+                    (0., 0., 0.)
+                }
+            })
+            .collect();
+
+        // Blur horizontally, then vertically.
+        let n = 2 * self.radius + 1;
+        let kernel = vec![1. / n as f32; n];
+        let blurred = convolve_pass(&bright, w, h, 1, w, &kernel);
+        let blurred = convolve_pass(&blurred, h, w, w, 1, &kernel);
+
+        // Blend the glow back in.
+        let intensity = self.intensity as f32;
+        let glow = src.pixels.iter().zip(blurred.iter());
+        let pixels = dst.pixels.iter_mut().zip(glow);
+        for (out, (&(r, g, b), &(br, bg, bb))) in pixels {
+            *out = (
+                (r as f32 + br * intensity * 255.).min(255.) as u8,
+                (g as f32 + bg * intensity * 255.).min(255.) as u8,
+                (b as f32 + bb * intensity * 255.).min(255.) as u8
+            );
+        }
+    }
+}
+
+
+// Darkens creases and corners by screen-space ambient occlusion. Each pixel
+// tests `samples` random points within `radius` of it, in the hemisphere
+// around the surface normal estimated from the depth buffer; the fraction
+// that lies behind nearer geometry darkens it. Depths are taken to be in
+// pixel units, as they are when no perspective transform is applied.
+// Occluders less than `bias` in front are ignored, which keeps surfaces from
+// shadowing themselves.
+#[cfg(feature = "ssao")]
+pub struct SsaoFilter {
+    pub samples: usize,
+    pub radius: f64,
+    pub bias: f64,
+}
+
+#[cfg(feature = "ssao")]
+impl SsaoFilter {
+    pub fn new(samples: usize, radius: f64, bias: f64) -> SsaoFilter {
+        SsaoFilter {
+            samples: samples,
+            radius: radius,
+            bias: bias,
+        }
+    }
+}
+
+#[cfg(feature = "ssao")]
+impl PostProcess for SsaoFilter {
+    fn apply(&self, src: &Texture, dst: &mut Texture) {
+        use rand::Rng;
+        use rand::SeedableRng;
+        use rand::XorShiftRng;
+
+        // A fixed seed keeps the noise from flickering between frames.
+        let mut rng = XorShiftRng::from_seed(
+            [0x193a_6754, 0xa8a7_d469, 0x9783_0e05, 0x113b_a7bb]
+        );
+        let mut kernel = Vec::with_capacity(self.samples);
+        while kernel.len() < self.samples {
+            let x = rng.gen_range(-1., 1.);
+            let y = rng.gen_range(-1., 1.);
+            let z = -rng.gen::<f64>();
+            let len2 = x * x + y * y + z * z;
+            if len2 > 1. || len2 == 0. { continue }
+
+            // Crowd the samples towards the center, where occluders matter
+            // most.
+            let t = (kernel.len() + 1) as f64 / self.samples as f64;
+            let scale = self.radius * (0.1 + 0.9 * t * t);
+            kernel.push((x * scale, y * scale, z * scale));
+        }
+
+        let w = src.w as i64;
+        let h = src.h as i64;
+        let depth = |x: i64, y: i64| {
+            src.read_depth(x as PixCoord, y as PixCoord)
+                .unwrap_or(f64::INFINITY)
+        };
+        // Depth slope between a pixel and its neighbors on either side,
+        // taken from the flatter side so edges don't tilt the normal.
+        let slope = |z: f64, before: f64, after: f64| {
+            let (a, b) = (z - before, after - z);
+            match (a.is_finite(), b.is_finite()) {
+                (true, true) => if a.abs() < b.abs() { a } else { b },
+                (true, false) => a,
+                (false, true) => b,
+                (false, false) => 0.,
+            }
+        };
+
+        for y in 0 .. h {
+            for x in 0 .. w {
+                let i = (y * w + x) as usize;
+                let z = depth(x, y);
+                if !z.is_finite() {
+                    dst.pixels[i] = src.pixels[i];
+                    continue;
+                }
+                let normal = (
+                    slope(z, depth(x - 1, y), depth(x + 1, y)),
+                    slope(z, depth(x, y - 1), depth(x, y + 1)),
+                    -1.
+                );
+
+                // Turn the kernel by a random angle per pixel, trading
+                // banding for noise.
+                let angle = rng.gen::<f64>() * 2. * f64::consts::PI;
+                let (sin, cos) = angle.sin_cos();
+                let mut occlusion = 0.;
+                for &(kx, ky, kz) in &kernel {
+                    let (kx, ky) = (kx * cos - ky * sin, kx * sin + ky * cos);
+                    // Mirror samples behind the surface to the front.
+                    let side = kx * normal.0 + ky * normal.1 + kz * normal.2;
+                    let flip = if side < 0. { -1. } else { 1. };
+                    let (kx, ky, kz) = (kx * flip, ky * flip, kz * flip);
+
+                    let sx = x as f64 + kx;
+                    let sy = y as f64 + ky;
+                    let stored = match src.read_depth(
+                        sx.round() as PixCoord,
+                        sy.round() as PixCoord
+                    ) {
+                        Some(stored) => stored,
+                        None => continue,
+                    };
+                    if stored < z + kz - self.bias {
+                        // Occluders far in front likely belong to another
+                        // object, so they count for less.
+                        occlusion += (self.radius / (z - stored)).min(1.);
+                    }
+                }
+
+                let factor = 1. - occlusion / kernel.len().max(1) as f64;
+                let (r, g, b) = src.pixels[i];
+                dst.pixels[i] = (
+                    (r as f64 * factor) as u8,
+                    (g as f64 * factor) as u8,
+                    (b as f64 * factor) as u8
+                );
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixel::Pixel;
+
+    fn apply<P: PostProcess>(pp: &P, src: &Texture) -> Texture {
+        let mut dst = src.clone();
+        pp.apply(src, &mut dst);
+        dst
+    }
+
+    fn column(t: &Texture, x: usize) -> Vec<Pixel> {
+        t.rows().map(|row| row[x]).collect()
+    }
+
+    #[test]
+    fn sobel_finds_vertical_edge() {
+        // Black on the left three columns, white on the right three.
+        let mut src = Texture::new(6, 4);
+        for row in src.pixels.chunks_mut(6) {
+            for p in row[3 ..].iter_mut() { *p = pixel::WHITE }
+        }
+
+        let out = apply(&SobelFilter::new(0.5), &src);
+        for x in 0 .. 6 {
+            let expected = if x == 2 || x == 3 {
+                pixel::WHITE
+            } else {
+                pixel::BLACK
+            };
+            let col = column(&out, x);
+            assert!(col.iter().all(|&p| p == expected), "x = {}", x);
+        }
+    }
+
+    #[test]
+    fn sobel_without_threshold_grades_edges() {
+        let mut src = Texture::new(6, 4);
+        for row in src.pixels.chunks_mut(6) {
+            for p in row[3 ..].iter_mut() { *p = (128, 128, 128) }
+        }
+
+        let out = apply(&SobelFilter::new(0.), &src);
+        let edge = column(&out, 2)[0];
+        assert!(edge.0 > 0 && edge.0 < 255);
+        assert_eq!(column(&out, 0)[0], pixel::BLACK);
+        assert_eq!(column(&out, 5)[0], pixel::BLACK);
+    }
+
+    #[test]
+    fn scanlines_darken_alternate_rows() {
+        let mut src = Texture::new(3, 4);
+        src.set_all_pixels((200, 200, 200));
+
+        let out = apply(&ScanlineFilter::new(0.5, 2), &src);
+        let rows: Vec<&[Pixel]> = out.rows().collect();
+        for (y, row) in rows.iter().enumerate() {
+            let expected = if y % 2 == 0 { 100 } else { 200 };
+            let expected = (expected, expected, expected);
+            assert!(row.iter().all(|&p| p == expected), "y = {}", y);
+        }
+    }
+
+    #[test]
+    fn scanlines_at_full_intensity_are_black() {
+        let mut src = Texture::new(2, 3);
+        src.set_all_pixels(pixel::WHITE);
+
+        let out = apply(&ScanlineFilter::new(1., 3), &src);
+        assert_eq!(out.read_pixel(0, 0), Some(pixel::BLACK));
+        assert_eq!(out.read_pixel(0, 1), Some(pixel::WHITE));
+        assert_eq!(out.read_pixel(0, 2), Some(pixel::WHITE));
+    }
+
+    #[test]
+    fn scanlines_on_an_empty_frame() {
+        let src = Texture::new(0, 3);
+        let out = apply(&ScanlineFilter::new(0.5, 2), &src);
+        assert!(out.pixels.is_empty());
+    }
+
+    #[test]
+    fn bloom_spreads_a_bright_pixel() {
+        let mut src = Texture::new(9, 9);
+        src.set_pixel(4, 4, 0., pixel::WHITE);
+        let bloom = BloomFilter { radius: 2, ..BloomFilter::new() };
+
+        let out = apply(&bloom, &src);
+        // The pixel itself stays white, everything within the blur radius
+        // picks up a glow of 1/25 of it, and the rest stays black.
+        assert_eq!(out.read_pixel(4, 4), Some(pixel::WHITE));
+        for y in 0 .. 9 {
+            for x in 0 .. 9 {
+                if (x, y) == (4, 4) { continue }
+                let near = (x - 4i16).abs() <= 2 && (y - 4i16).abs() <= 2;
+                let expected = if near { (10, 10, 10) } else { pixel::BLACK };
+                assert_eq!(out.read_pixel(x, y), Some(expected));
+            }
+        }
+    }
+
+    #[test]
+    fn bloom_ignores_dim_pixels() {
+        let mut src = Texture::new(5, 5);
+        src.set_pixel(2, 2, 0., (100, 100, 100));
+
+        let out = apply(&BloomFilter::new(), &src);
+        assert!(out.compare(&src, 0));
+    }
+
+    #[cfg(feature = "ssao")]
+    #[test]
+    fn ssao_darkens_concave_corners() {
+        // A flat floor at depth 10 on the right, with a block raised to depth
+        // 5 on the left, so the floor meets the block's side in a corner.
+        let mut src = Texture::new(24, 8);
+        for y in 0 .. 8 {
+            for x in 0 .. 24 {
+                let z = if x < 8 { 5. } else { 10. };
+                src.set_pixel(x, y, z, pixel::WHITE);
+            }
+        }
+
+        let ssao = SsaoFilter::new(32, 6., 0.01);
+        let out = apply(&ssao, &src);
+        let gray = |x: PixCoord| out.read_pixel(x, 4).unwrap().0;
+        // Darkest in the corner, fading out along the floor.
+        assert!(gray(8) < 200, "corner {}", gray(8));
+        assert!(gray(8) < gray(9) && gray(9) < gray(11));
+        assert_eq!(gray(20), 255);
+        // The top of the block is convex and stays lit.
+        assert_eq!(gray(3), 255);
+        // The background has no depth and is left alone.
+        let empty = Texture::new(4, 4);
+        assert!(apply(&ssao, &empty).compare(&empty, 0));
+    }
+}
@@ -2,12 +2,30 @@ use std::cmp::Ordering::Equal;
 use std::error;
 use std::f64;
 use std::mem;
+use std::ops::Deref;
+use std::ops::DerefMut;
 
+use font;
+use font::BitmapFont;
+use mesh;
+use mesh::IndexBuffer;
+use mesh::LODMesh;
+use mesh::Mesh;
+use mesh::primitives;
+use mesh::VertexBuffer;
 use pixel;
 use pixel::Pixel;
+use postprocess::PostProcess;
 use screen::Screen;
+use texture::BlendMode;
+use texture::DepthTest;
+use texture::FilterMode;
+use texture::MAX_TEXTURE_PIXELS;
 use texture::Texture;
+use texture::TextureHdr;
+use texture::TextureView;
 use types::*;
+use utils::clamp;
 
 
 macro_rules! do_with_color {
@@ -20,22 +38,275 @@ macro_rules! do_with_color {
 }
 
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum LightingMode {
     NoShading,
     FlatShading,
 }
 
+// Which faces of filled triangles are discarded. Front faces wind clockwise
+// on screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CullMode {
+    Back,
+    Front,
+    None,
+}
+
+// Whether filled triangles are filled, outlined in the wireframe color, or
+// both.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderMode {
+    Solid,
+    Wireframe,
+    SolidWireframe,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FogEquation {
+    Linear,
+    Exp,
+    Exp2,
+}
+
+// Blends filled triangles towards `color` with their distance from the
+// view-space origin, where the camera sits. Linear fog is clear up to
+// `start` and opaque from `end` on; Exp and Exp2 let through
+// exp(-density * d) and exp(-(density * d)^2) of the surface color.
+#[derive(Clone, Copy, Debug)]
+pub struct Fog {
+    pub color: Pixel,
+    pub equation: FogEquation,
+    pub start: f64,
+    pub end: f64,
+    pub density: f64,
+}
+
+impl Fog {
+    pub fn linear(color: Pixel, start: f64, end: f64) -> Fog {
+        Fog {
+            color: color,
+            equation: FogEquation::Linear,
+            start: start,
+            end: end,
+            density: 0.,
+        }
+    }
+
+    pub fn exp(color: Pixel, density: f64) -> Fog {
+        Fog {
+            color: color,
+            equation: FogEquation::Exp,
+            start: 0.,
+            end: 0.,
+            density: density,
+        }
+    }
+
+    pub fn exp2(color: Pixel, density: f64) -> Fog {
+        Fog { equation: FogEquation::Exp2, ..Fog::exp(color, density) }
+    }
+
+    // The fraction of the surface color left at distance `d`.
+    pub fn visibility(&self, d: f64) -> f64 {
+        let v = match self.equation {
+            FogEquation::Linear => {
+                if self.end > self.start {
+                    (self.end - d) / (self.end - self.start)
+                } else if d < self.start {
+                    1.
+                } else {
+                    0.
+                }
+            },
+            FogEquation::Exp => (-self.density * d).exp(),
+            FogEquation::Exp2 => (-(self.density * d).powi(2)).exp(),
+        };
+        clamp(v, 0., 1.)
+    }
+
+    fn apply(&self, color: Pixel, d: f64) -> Pixel {
+        pixel::lerp(self.color, color, self.visibility(d))
+    }
+}
+
+// The color a face is filled with: one for all of it, one per vertex
+// blended across it (Gouraud shading), or lit per pixel (Phong shading).
+#[derive(Clone, Copy)]
+enum FaceColor {
+    Flat(Pixel),
+    Gouraud([Pixel; 3]),
+    Phong(PhongFace),
+}
+
+impl FaceColor {
+    // The color at barycentric coordinates (b1, b2, b3), which is the point
+    // `p` after the model transform.
+    fn at(self, p: Point, b1: f64, b2: f64, b3: f64) -> Pixel {
+        match self {
+            FaceColor::Flat(color) => color,
+            FaceColor::Gouraud([c1, c2, c3]) => {
+                let mix = |x: u8, y: u8, z: u8| {
+                    let v = b1 * x as f64 + b2 * y as f64 + b3 * z as f64;
+                    clamp(v.round(), 0., 255.) as u8
+                };
+                (
+                    mix(c1.0, c2.0, c3.0),
+                    mix(c1.1, c2.1, c3.1),
+                    mix(c1.2, c2.2, c3.2)
+                )
+            },
+            FaceColor::Phong(face) => face.at(p, b1, b2, b3),
+        }
+    }
+
+    // The colors of `piece`, a part of `t` such as one left by clipping.
+    fn of_piece(self, t: Triangle, piece: Triangle) -> FaceColor {
+        match self {
+            FaceColor::Flat(_) => self,
+            FaceColor::Gouraud(_) => {
+                let color_at = |p: Point| {
+                    let (b1, b2, b3) = barycentric(t, p);
+                    self.at(p, b1, b2, b3)
+                };
+                FaceColor::Gouraud(
+                    [color_at(piece.p1), color_at(piece.p2), color_at(piece.p3)]
+                )
+            },
+            FaceColor::Phong(face) =>
+                FaceColor::Phong(face.of_piece(t, piece)),
+        }
+    }
+}
+
+// A face lit per pixel by the normal interpolated from its vertex normals,
+// for a diffuse term from `light` in `color` plus a white highlight seen
+// from `eye`. Positions and normals are after the model transform.
+#[derive(Clone, Copy)]
+struct PhongFace {
+    normals: [Point; 3],
+    light: Point,
+    eye: Point,
+    color: Pixel,
+    ambient: f64,
+    emissive: Pixel,
+    shininess: f64,
+}
+
+impl PhongFace {
+    fn at(&self, p: Point, b1: f64, b2: f64, b3: f64) -> Pixel {
+        let [n1, n2, n3] = self.normals;
+        let n = (n1 * b1 + n2 * b2 + n3 * b3).normalized();
+        let l = (self.light - p).normalized();
+        let v = (self.eye - p).normalized();
+
+        let diffuse = n.dot(l).max(0.);
+        let r = n * (2. * n.dot(l)) - l;
+        let specular = if diffuse > 0. {
+            r.dot(v).max(0.).powf(self.shininess)
+        } else {
+            0.
+        };
+
+        let light_mag = self.ambient + (1. - self.ambient) * diffuse;
+        let channel = |c: u8| {
+            clamp(c as f64 * light_mag + 255. * specular, 0., 255.) as u8
+        };
+        let color = self.color;
+        let lit = (channel(color.0), channel(color.1), channel(color.2));
+        pixel::blend_additive(lit, self.emissive)
+    }
+
+    // The normals of `piece`, a part of `t`.
+    fn of_piece(self, t: Triangle, piece: Triangle) -> PhongFace {
+        let [n1, n2, n3] = self.normals;
+        let normal_at = |p: Point| {
+            let (b1, b2, b3) = barycentric(t, p);
+            n1 * b1 + n2 * b2 + n3 * b3
+        };
+        PhongFace {
+            normals: [
+                normal_at(piece.p1),
+                normal_at(piece.p2),
+                normal_at(piece.p3),
+            ],
+            ..self
+        }
+    }
+}
+
+// The settings saved by `Renderer::push_state`: everything that changes how
+// later draws come out, but not the color stack, post-processes or the render
+// target.
+#[derive(Clone)]
+pub struct RendererState {
+    transform: Transform,
+    model: Transform,
+    viewport: Option<(PixCoord, PixCoord, Dimension, Dimension)>,
+    color: Pixel,
+
+    light: Point,
+    lighting_mode: LightingMode,
+    ambient: f64,
+    emissive: Pixel,
+    shininess: f64,
+    fog: Option<Fog>,
+    point_size: f64,
+    clear_color: Pixel,
+
+    gamma_correction: bool,
+    grayscale: bool,
+    cull_mode: CullMode,
+    render_mode: RenderMode,
+    wireframe_color: Pixel,
+    line_antialiasing: bool,
+    line_width: f64,
+    clip_planes: Vec<(Point, f64)>,
+
+    font: BitmapFont,
+    blend_mode: BlendMode,
+    depth_test: DepthTest,
+}
+
 pub struct Renderer<S>
     where S: Screen
 {
     screen: S,
+    // The render target while one is set, otherwise the screen's frame.
     texture: Texture,
+    // The screen's frame while a render target is set.
+    screen_texture: Option<Texture>,
 
     transform: Transform,
+    // Applied before `transform`. Lighting, fog, culling and clip planes
+    // work in the space it maps to.
+    model: Transform,
+    viewport: Option<(PixCoord, PixCoord, Dimension, Dimension)>,
     color: Pixel,
+    color_stack: Vec<Pixel>,
+    state_stack: Vec<RendererState>,
 
     light: Point,
     lighting_mode: LightingMode,
+    ambient: f64,
+    emissive: Pixel,
+    shininess: f64,
+    fog: Option<Fog>,
+    point_size: f64,
+    clear_color: Pixel,
+
+    post_processes: Vec<Box<PostProcess>>,
+    gamma_correction: bool,
+    grayscale: bool,
+    cull_mode: CullMode,
+    render_mode: RenderMode,
+    wireframe_color: Pixel,
+    line_antialiasing: bool,
+    line_width: f64,
+    line_pattern_phase: usize,
+    clip_planes: Vec<(Point, f64)>,
+
+    font: BitmapFont,
 }
 
 #[allow(dead_code)]
@@ -43,36 +314,135 @@ impl<S> Renderer<S>
     where S: Screen
 {
     pub fn new(screen: S) -> Renderer<S> {
-        let w = screen.width();
-        let h = screen.height();
+        let texture = Texture::new(screen.width(), screen.height());
+        Renderer::with_texture(screen, texture)
+    }
+
+    // Like `new`, but None if the screen needs a texture larger than
+    // `MAX_TEXTURE_PIXELS`.
+    pub fn try_new(screen: S) -> Option<Renderer<S>> {
+        Texture::try_new(screen.width(), screen.height())
+            .map(|texture| Renderer::with_texture(screen, texture))
+    }
 
+    fn with_texture(screen: S, texture: Texture) -> Renderer<S> {
         Renderer {
             screen: screen,
-            texture: Texture::new(w, h),
+            texture: texture,
+            screen_texture: None,
 
             transform: Transform::identity(),
+            model: Transform::identity(),
+            viewport: None,
             color: pixel::WHITE,
+            color_stack: Vec::new(),
+            state_stack: Vec::new(),
 
             light: pt![0., 0., 0.],
             lighting_mode: LightingMode::NoShading,
+            ambient: 0.,
+            emissive: pixel::BLACK,
+            shininess: 32.,
+            fog: None,
+            point_size: 7.,
+            clear_color: pixel::BLACK,
+
+            post_processes: Vec::new(),
+            gamma_correction: false,
+            grayscale: false,
+            cull_mode: CullMode::Back,
+            render_mode: RenderMode::Solid,
+            wireframe_color: pixel::WHITE,
+            line_antialiasing: false,
+            line_width: 1.,
+            line_pattern_phase: 0,
+            clip_planes: Vec::new(),
+
+            font: BitmapFont::builtin(),
         }
     }
 
     pub fn draw_point(&mut self, p: Point) {
-        let p = p * self.transform;
-        let d = 7;
-        for row in 0 .. d {
-            self.texture.set_row(
-                p.x as PixCoord - d / 2,
-                p.x as PixCoord + d / 2,
-                p.y as PixCoord + row - d / 2,
-                f64::NEG_INFINITY,
-                f64::NEG_INFINITY,
-                self.color
-            );
+        let size = self.point_size;
+        self.draw_point_with_size(p, size);
+    }
+
+    // Draws `p` as a `size` pixel square in the current color. Under a
+    // perspective transform the square shrinks with distance, `size` being
+    // its side at a w of 1, and points behind the camera are skipped.
+    pub fn draw_point_with_size(&mut self, p: Point, size: f64) {
+        let w = homogeneous_w(p, self.transform * self.model);
+        if w <= 0. { return }
+        let color = self.color;
+        self.draw_square(p, particle_side(size / w), color);
+    }
+
+    // Draws each point as a single depth-tested pixel in the current color,
+    // which stays cheap for clouds of millions of points.
+    pub fn draw_point_cloud(&mut self, points: &[Point]) {
+        let transform = self.screen_transform();
+        let color = self.output_color(self.color);
+        for &p in points {
+            let p = p * transform;
+            if p.x.is_nan() || p.y.is_nan() { continue }
+            // Flooring keeps points just left of or above the texture off it.
+            let (x, y) = (p.x.floor() as PixCoord, p.y.floor() as PixCoord);
+            self.texture.set_pixel(x, y, p.z, color);
+        }
+    }
+
+    // Draws each position as a `size` pixel square in the current color, on
+    // top of everything else.
+    pub fn draw_particles(&mut self, positions: &[Point], size: f64) {
+        let side = particle_side(size);
+        let color = self.color;
+        for &p in positions {
+            self.draw_square(p, side, color);
+        }
+    }
+
+    // Like `draw_particles`, with one color per position.
+    pub fn draw_particles_colored(
+        &mut self,
+        positions: &[Point],
+        colors: &[Pixel],
+        size: f64
+    ) {
+        assert!(positions.len() == colors.len());
+        let side = particle_side(size);
+        for (&p, &color) in positions.iter().zip(colors.iter()) {
+            self.draw_square(p, side, color);
         }
     }
 
+    // A `side` pixel square centered on where `p` lands on screen.
+    fn draw_square(&mut self, p: Point, side: Dimension, color: Pixel) {
+        let p = p * self.screen_transform();
+        if !p.x.is_finite() || !p.y.is_finite() { return }
+
+        // Far off screen or huge squares leave the range of PixCoord, so the
+        // corners are found in i64 and clamped to the texture.
+        let half = side as i64 / 2;
+        let span = |c: f64, len: Dimension| {
+            let start = (c as i64).saturating_sub(half);
+            let end = start.saturating_add(side as i64);
+            (start.max(0), end.min(len as i64))
+        };
+        let (x0, x1) = span(p.x, self.texture.w);
+        let (y0, y1) = span(p.y, self.texture.h);
+        let max = PixCoord::MAX as i64;
+        if x0 >= x1 || y0 >= y1 || x0 > max || y0 > max { return }
+
+        let color = self.output_color(color);
+        self.texture.fill_region(
+            x0 as PixCoord,
+            y0 as PixCoord,
+            (x1 - x0) as Dimension,
+            (y1 - y0) as Dimension,
+            color
+        );
+    }
+
     fn draw_point_with_transform(&mut self, p: Point, transform: Transform) {
         let old_transform = self.transform;
         self.transform = transform;
@@ -81,8 +451,152 @@ impl<S> Renderer<S>
     }
 
     pub fn draw_line(&mut self, p1: Point, p2: Point) {
-        let p1 = p1 * self.transform;
-        let p2 = p2 * self.transform;
+        if self.line_antialiasing { return self.draw_line_aa(p1, p2) }
+        let transform = self.screen_transform();
+        self.draw_screen_line(p1 * transform, p2 * transform);
+    }
+
+    // Like `draw_line`, but only every first `dash_len` of each
+    // `dash_len + gap_len` pixels along the line is drawn. The place in the
+    // pattern carries over between calls, so the dashes of connected lines
+    // line up; `reset_line_pattern` starts over with a dash.
+    pub fn draw_dashed_line(
+        &mut self,
+        p1: Point,
+        p2: Point,
+        dash_len: usize,
+        gap_len: usize
+    ) {
+        let transform = self.screen_transform();
+        let dash = Some((dash_len, gap_len));
+        self.draw_patterned_screen_line(p1 * transform, p2 * transform, dash);
+    }
+
+    pub fn reset_line_pattern(&mut self) {
+        self.line_pattern_phase = 0;
+    }
+
+    // Like `draw_line`, but antialiased with Xiaolin Wu's algorithm: each
+    // step covers the two pixels nearest the line, blended into what is
+    // already there by how much of each the line covers.
+    pub fn draw_line_aa(&mut self, p1: Point, p2: Point) {
+        let transform = self.screen_transform();
+        let (p1, p2) = (p1 * transform, p2 * transform);
+        // Move pixel centers onto whole coordinates.
+        let (mut x0, mut y0) = (p1.x - 0.5, p1.y - 0.5);
+        let (mut x1, mut y1) = (p2.x - 0.5, p2.y - 0.5);
+        if !(x0 + y0 + x1 + y1).is_finite() { return }
+
+        // Step along the major axis, left to right.
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            mem::swap(&mut x0, &mut y0);
+            mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            mem::swap(&mut x0, &mut x1);
+            mem::swap(&mut y0, &mut y1);
+        }
+        let dx = x1 - x0;
+        let gradient = if dx == 0. { 1. } else { (y1 - y0) / dx };
+        let color = self.output_color(self.color);
+        let fpart = |v: f64| v - v.floor();
+
+        // The endpoints, weighted by how much of their pixel the line spans.
+        let start = x0.round();
+        let end = x1.round();
+        let y_start = y0 + gradient * (start - x0);
+        let y_end = y0 + gradient * (end - x0);
+        let start_gap = 1. - fpart(x0 + 0.5);
+        let end_gap = fpart(x1 + 0.5);
+        self.plot_wu_pair(steep, start, y_start, start_gap, color);
+        self.plot_wu_pair(steep, end, y_end, end_gap, color);
+
+        // Skip the steps that fall outside the texture.
+        let limit = if steep { self.texture.h } else { self.texture.w } as f64;
+        let first = (start + 1.).max(-1.);
+        let last = (end - 1.).min(limit);
+        let mut x = first;
+        while x <= last {
+            let y = y_start + gradient * (x - start);
+            self.plot_wu_pair(steep, x, y, 1., color);
+            x += 1.;
+        }
+    }
+
+    // Covers the pixels on either side of the line at (x, y) in Wu's
+    // stepping space, with x and y swapped back if `steep`.
+    fn plot_wu_pair(
+        &mut self,
+        steep: bool,
+        x: f64,
+        y: f64,
+        coverage: f64,
+        color: Pixel
+    ) {
+        let below = y.floor();
+        let frac = y - below;
+        let pixels = [(below, 1. - frac), (below + 1., frac)];
+        for &(y, weight) in &pixels {
+            let (px, py) = if steep { (y, x) } else { (x, y) };
+            let alpha = coverage * weight;
+            if alpha <= 0. || px < 0. || py < 0. { continue }
+            if px > PixCoord::MAX as f64 || py > PixCoord::MAX as f64 {
+                continue;
+            }
+            self.texture.blend_pixel(
+                px as PixCoord,
+                py as PixCoord,
+                f64::NEG_INFINITY,
+                color,
+                alpha
+            );
+        }
+    }
+
+    // Lines wider than a pixel are drawn as several thin ones side by side,
+    // a pixel apart along the minor axis so they leave no gaps. A thin line
+    // is max(|dx|, |dy|) / len across, under a pixel for diagonals, so there
+    // are as many of them as it takes to make up the width across the line.
+    fn draw_screen_line(&mut self, p1: Point, p2: Point) {
+        self.draw_patterned_screen_line(p1, p2, None);
+    }
+
+    // `dash` is as for `draw_dashed_line`, or None for a solid line.
+    fn draw_patterned_screen_line(
+        &mut self,
+        p1: Point,
+        p2: Point,
+        dash: Option<(usize, usize)>
+    ) {
+        let (dx, dy) = (p2.x - p1.x, p2.y - p1.y);
+        let len = (dx * dx + dy * dy).sqrt();
+        if self.line_width <= 1. || len.is_nan() || len <= 0. {
+            return self.draw_thin_screen_line(p1, p2, dash);
+        }
+
+        let thin = dx.abs().max(dy.abs()) / len;
+        let count = (self.line_width / thin).round() as i64;
+        let step = if dx.abs() >= dy.abs() {
+            pt_2d![0., 1.]
+        } else {
+            pt_2d![1., 0.]
+        };
+        // Start every pass at the same place in the dash pattern.
+        let phase = self.line_pattern_phase;
+        for i in 0 .. count {
+            self.line_pattern_phase = phase;
+            let offset = step * (i - count / 2) as f64;
+            self.draw_thin_screen_line(p1 + offset, p2 + offset, dash);
+        }
+    }
+
+    fn draw_thin_screen_line(
+        &mut self,
+        p1: Point,
+        p2: Point,
+        dash: Option<(usize, usize)>
+    ) {
         let p1x = p1.x as PixCoord;
         let p1y = p1.y as PixCoord;
         let p2x = p2.x as PixCoord;
@@ -113,13 +627,24 @@ impl<S> Renderer<S>
                 error += adx;
             }
 
+            let lit = match dash {
+                Some((dash_len, gap_len)) => {
+                    let period = (dash_len + gap_len).max(1);
+                    let lit = self.line_pattern_phase % period < dash_len;
+                    self.line_pattern_phase += 1;
+                    lit
+                },
+                None => true,
+            };
             // FIXME: Do depth lerping.
-            self.texture.set_pixel(
-                x,
-                y,
-                f64::INFINITY,
-                self.color
-            );
+            if lit {
+                self.texture.set_pixel(
+                    x,
+                    y,
+                    f64::NEG_INFINITY,
+                    self.output_color(self.color)
+                );
+            }
 
             if adx >= ady {
                 if x == p2x { break }
@@ -143,157 +668,2602 @@ impl<S> Renderer<S>
         self.transform = old_transform;
     }
 
+    // Draws a line and writes `label1` and `label2` next to its endpoints,
+    // offset down and to the right so they don't cover them. Labels of
+    // endpoints off screen are left out.
+    pub fn draw_labeled_line(
+        &mut self,
+        p1: Point,
+        label1: &str,
+        p2: Point,
+        label2: &str
+    ) {
+        const LABEL_OFFSET: PixCoord = 4;
+        self.draw_line(p1, p2);
+        let transform = self.screen_transform();
+        for &(p, label) in &[(p1, label1), (p2, label2)] {
+            let p = p * transform;
+            let on_screen = p.x >= 0. && p.x < self.texture.w as f64
+                && p.y >= 0. && p.y < self.texture.h as f64;
+            if !on_screen { continue }
+            self.draw_text(
+                label,
+                p.x as PixCoord + LABEL_OFFSET,
+                p.y as PixCoord + LABEL_OFFSET
+            );
+        }
+    }
+
+    // Draws a line between two pixels in screen space, ignoring the current
+    // transform.
+    pub fn draw_line_2d(
+        &mut self,
+        x1: PixCoord,
+        y1: PixCoord,
+        x2: PixCoord,
+        y2: PixCoord
+    ) {
+        self.draw_screen_line(
+            pt_2d![x1 as Coord, y1 as Coord],
+            pt_2d![x2 as Coord, y2 as Coord]
+        );
+    }
+
     pub fn draw_triangle(&mut self, t: Triangle) {
         self.draw_line(t.p1, t.p2);
         self.draw_line(t.p2, t.p3);
         self.draw_line(t.p3, t.p1);
     }
 
-    pub fn fill_triangle(&mut self, t: Triangle) {
-        // Backface culling.
-        let centroid = (t.p1 + t.p2 + t.p3) * (1. / 3.);
-        let ct = t * self.transform;
-        if ct.normal().dot(centroid) >= 0. { return }
-
-        // Sort points by y coord.
-        let mut pts = ct.to_arr();
-        pts.sort_by(
-            |p1, p2|
-            p1.y.partial_cmp(&p2.y)
-                .unwrap_or(Equal)
-        );
-        let (top, middle, bot) = (pts[0], pts[1], pts[2]);
-
-        // Compute color of triangle based on light.
-        let old_color = self.color;
-        self.color = self.light_triangle(t, old_color);
-
-        const EPSILON: f64 = 1.;
-        if middle.y - top.y < EPSILON {
-            self.fill_top_flat_triangle(Triangle::from_arr(pts));
-        } else if bot.y - middle.y < EPSILON {
-            self.fill_bottom_flat_triangle(Triangle::from_arr(pts));
-        } else {
-            let dy_mid: Coord = middle.y - top.y;
-            let dy_bot: Coord = bot.y - top.y;
-            let dx_bot: Coord = bot.x - top.x;
-            let dz_bot: Coord = bot.z - top.z;
-
-            let v4 = pt![
-                top.x + dx_bot * dy_mid / dy_bot,
-                middle.y,
-                top.z + dz_bot * dy_mid / dy_bot
-            ];
-            self.fill_bottom_flat_triangle(trigon![top, middle, v4]);
-            self.fill_top_flat_triangle(trigon![middle, v4, bot]);
+    // Draws the open polyline through `points`.
+    pub fn draw_line_strip(&mut self, points: &[Point]) {
+        for pair in points.windows(2) {
+            self.draw_line(pair[0], pair[1]);
         }
-
-        self.color = old_color;
     }
 
-    fn light_triangle(&self, t: Triangle, color: Pixel) -> Pixel {
-        match self.lighting_mode {
-            LightingMode::NoShading => color,
-            LightingMode::FlatShading => {
-                let centroid = (t.p1 + t.p2 + t.p3) * (1. / 3.);
-                let light_dir = (self.light - centroid).normalized();
-                let light_mag = light_dir.dot(t.normal()).max(0.);
-                let (r, g, b) = color;
-                (
-                    (r as f64 * light_mag) as u8,
-                    (g as f64 * light_mag) as u8,
-                    (b as f64 * light_mag) as u8
-                )
-            },
+    // Like `draw_line_strip`, but also joins the last point back to the first.
+    pub fn draw_line_loop(&mut self, points: &[Point]) {
+        self.draw_line_strip(points);
+        if points.len() > 2 {
+            self.draw_line(points[points.len() - 1], points[0]);
         }
     }
 
-    fn fill_bottom_flat_triangle(&mut self, t: Triangle) {
-        let (top, mut left, mut right) = t.to_tuple();
-        if left.x > right.x { mem::swap(&mut left, &mut right) }
+    // Outlines the convex hull of where `points` land on screen.
+    pub fn draw_convex_hull(&mut self, points: &[Point]) {
+        let transform = self.screen_transform();
+        let projected: Vec<(f64, f64)> = points.iter()
+            .map(|&p| {
+                let p = p * transform;
+                (p.x, p.y)
+            })
+            .collect();
+        let hull: Vec<Point> = mesh::convex_hull_2d(&projected).iter()
+            .map(|&(x, y)| pt_2d![x, y])
+            .collect();
 
-        for y in top.y as PixCoord .. left.y as PixCoord {
-            let t = (y - top.y as PixCoord) as Coord / (left.y - top.y);
+        // The hull is in screen space already.
+        let (old_transform, old_viewport) = (self.transform, self.viewport);
+        let old_model = self.model;
+        self.transform = Transform::identity();
+        self.model = Transform::identity();
+        self.viewport = None;
+        self.draw_line_loop(&hull);
+        self.transform = old_transform;
+        self.model = old_model;
+        self.viewport = old_viewport;
+    }
 
-            let z_left  = top.z + t * (left.z  - top.z);
-            let z_right = top.z + t * (right.z - top.z);
+    // Draws the closed outline through `vertices`.
+    pub fn draw_polygon(&mut self, vertices: &[Point]) {
+        self.draw_line_loop(vertices);
+    }
 
-            self.texture.set_row(
-                (top.x + (left.x  - top.x) * t) as PixCoord,
-                (top.x + (right.x - top.x) * t) as PixCoord,
-                y,
-                z_left,
-                z_right,
-                self.color
+    // Fills one triangle from `center` to each pair of neighboring points,
+    // including the last and first, so the fan closes.
+    pub fn draw_triangle_fan(&mut self, center: Point, points: &[Point]) {
+        for pair in points.windows(2) {
+            self.fill_triangle(trigon![center, pair[0], pair[1]]);
+        }
+        if points.len() > 2 {
+            self.fill_triangle(
+                trigon![center, points[points.len() - 1], points[0]]
             );
         }
     }
 
-    fn fill_top_flat_triangle(&mut self, t: Triangle) {
-        let (mut left, mut right, bot) = t.to_tuple();
-        if left.x > right.x { mem::swap(&mut left, &mut right) }
-
-        for y in left.y as PixCoord .. bot.y as PixCoord + 1 {
-            let t       = (y - left.y as PixCoord) as Coord / (bot.y - left.y);
-            let z_left  = left.z  + t * (bot.z - left.z);
-            let z_right = right.z + t * (bot.z - right.z);
+    // Fills triangle i from points i, i + 1 and i + 2, swapping the first two
+    // on odd triangles so the whole strip faces the same way, as GL
+    // triangle strips do.
+    pub fn draw_triangle_strip(&mut self, points: &[Point]) {
+        for (i, tri) in points.windows(3).enumerate() {
+            let t = if i % 2 == 0 {
+                trigon![tri[0], tri[1], tri[2]]
+            } else {
+                trigon![tri[1], tri[0], tri[2]]
+            };
+            self.fill_triangle(t);
+        }
+    }
 
-            self.texture.set_row(
-                (left.x  + (bot.x - left.x)  * t) as PixCoord,
-                (right.x + (bot.x - right.x) * t) as PixCoord,
-                y,
-                z_left,
-                z_right,
-                self.color
+    // Fills a convex polygon as a fan of triangles around the first vertex.
+    pub fn fill_polygon(&mut self, vertices: &[Point]) {
+        if vertices.len() < 3 { return }
+        for i in 1 .. vertices.len() - 1 {
+            self.fill_triangle(
+                trigon![vertices[0], vertices[i], vertices[i + 1]]
             );
         }
     }
 
-    pub fn clear(&mut self) {
-        self.texture.clear();
+    // Draws the curve as `steps` line segments.
+    pub fn draw_bezier_quadratic(
+        &mut self,
+        p0: Point,
+        p1: Point,
+        p2: Point,
+        steps: usize
+    ) {
+        let steps = steps.max(1);
+        let mut prev = p0;
+        for i in 1 .. steps + 1 {
+            let t = i as f64 / steps as f64;
+            let u = 1. - t;
+            let p = p0 * (u * u) + p1 * (2. * u * t) + p2 * (t * t);
+            self.draw_line(prev, p);
+            prev = p;
+        }
     }
 
-    pub fn display(&mut self) -> Result<(), Box<error::Error>> {
-        self.screen.display_texture(&self.texture)
+    // Draws the curve as `steps` line segments.
+    pub fn draw_bezier_cubic(
+        &mut self,
+        p0: Point,
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        steps: usize
+    ) {
+        let steps = steps.max(1);
+        let mut prev = p0;
+        for i in 1 .. steps + 1 {
+            let t = i as f64 / steps as f64;
+            let u = 1. - t;
+            let p = p0 * (u * u * u)
+                + p1 * (3. * u * u * t)
+                + p2 * (3. * u * t * t)
+                + p3 * (t * t * t);
+            self.draw_line(prev, p);
+            prev = p;
+        }
     }
 
+    // Fills the bicubic surface over `control_pts` as a grid of `u_steps` by
+    // `v_steps` quads, each split into two triangles. A triangle runs from
+    // (u, v) to (u, v + dv) to (u + du, v), which decides which side of the
+    // surface faces the camera for culling.
+    pub fn draw_bezier_surface(
+        &mut self,
+        control_pts: [[Point; 4]; 4],
+        u_steps: usize,
+        v_steps: usize
+    ) {
+        let (u_steps, v_steps) = (u_steps.max(1), v_steps.max(1));
+        let grid: Vec<Vec<Point>> = (0 .. u_steps + 1)
+            .map(|i| {
+                let u = i as f64 / u_steps as f64;
+                (0 .. v_steps + 1)
+                    .map(|j| {
+                        let v = j as f64 / v_steps as f64;
+                        bezier_surface_point(control_pts, u, v)
+                    })
+                    .collect()
+            })
+            .collect();
 
-    pub fn set_transform(&mut self, t: Transform) {
-        self.transform = t;
+        for i in 0 .. u_steps {
+            for j in 0 .. v_steps {
+                let (p00, p01) = (grid[i][j], grid[i][j + 1]);
+                let (p10, p11) = (grid[i + 1][j], grid[i + 1][j + 1]);
+                self.fill_triangle(trigon![p00, p01, p10]);
+                self.fill_triangle(trigon![p10, p01, p11]);
+            }
+        }
     }
 
-    pub fn clear_transform(&mut self) {
-        self.transform = Transform::identity();
+    // Draws the X, Y and Z axes of the current frame in red, green and blue.
+    pub fn draw_axes(&mut self, length: f64) {
+        let origin = pt![0., 0., 0.];
+        do_with_color!(self, pixel::RED, {
+            self.draw_line(origin, pt![length, 0., 0.]);
+        });
+        do_with_color!(self, pixel::GREEN, {
+            self.draw_line(origin, pt![0., length, 0.]);
+        });
+        do_with_color!(self, pixel::BLUE, {
+            self.draw_line(origin, pt![0., 0., length]);
+        });
     }
 
-    pub fn translate(&mut self, p: Point) {
-        self.transform = Transform::translate(p) * self.transform;
+    // Draws a square grid on the XZ plane centered at the origin, `size`
+    // across and cut into `divisions` cells in each direction.
+    pub fn draw_grid(&mut self, size: f64, divisions: usize) {
+        self.draw_grid_lines(size, divisions, false);
     }
 
-    pub fn rotate_x(&mut self, theta: f64) {
-        self.transform = Transform::rotate_x(theta) * self.transform;
+    // Like `draw_grid`, but the lines along the X and Z axes are drawn red
+    // and blue. They only exist for an even number of divisions.
+    pub fn draw_grid_with_axes(&mut self, size: f64, divisions: usize) {
+        self.draw_grid_lines(size, divisions, true);
     }
 
-    pub fn rotate_y(&mut self, theta: f64) {
-        self.transform = Transform::rotate_y(theta) * self.transform;
-    }
+    fn draw_grid_lines(&mut self, size: f64, divisions: usize, axes: bool) {
+        let divisions = divisions.max(1);
+        let half = size / 2.;
+        let spacing = size / divisions as f64;
+        for i in 0 .. divisions + 1 {
+            let offset = -half + i as f64 * spacing;
+            let on_axis = axes && 2 * i == divisions;
 
-    pub fn rotate_z(&mut self, theta: f64) {
-        self.transform = Transform::rotate_z(theta) * self.transform;
-    }
+            // Line parallel to the X axis.
+            let color = if on_axis { pixel::RED } else { self.color };
+            do_with_color!(self, color, {
+                self.draw_line(pt![-half, 0., offset], pt![half, 0., offset]);
+            });
+
+            // Line parallel to the Z axis.
+            let color = if on_axis { pixel::BLUE } else { self.color };
+            do_with_color!(self, color, {
+                self.draw_line(pt![offset, 0., -half], pt![offset, 0., half]);
+            });
+        }
+    }
+
+    // Draws the latitude and longitude lines of a sphere.
+    pub fn draw_sphere_wireframe(
+        &mut self,
+        center: Point,
+        radius: f64,
+        rings: usize,
+        sectors: usize
+    ) {
+        for (p1, p2) in primitives::sphere_wireframe(radius, rings, sectors) {
+            self.draw_line(center + p1, center + p2);
+        }
+    }
+
+    // Draws the 12 edges of the box.
+    pub fn draw_aabb(&mut self, aabb: &Aabb) {
+        self.draw_box_edges(&aabb.corners());
+    }
+
+    // Draws the 12 edges of the view frustum of `cam_transform`, a camera's
+    // projection-view matrix. Nothing is drawn if it cannot be inverted.
+    pub fn draw_frustum_wireframe(&mut self, cam_transform: Transform) {
+        if let Some(frustum) = Frustum::from_view_projection(cam_transform) {
+            self.draw_box_edges(&frustum.corners);
+        }
+    }
+
+    // Draws the edges between eight corners ordered like `Aabb::corners`.
+    fn draw_box_edges(&mut self, corners: &[Point; 8]) {
+        // Corners differing in exactly one bit share an edge.
+        for i in 0 .. corners.len() {
+            for &bit in &[1, 2, 4] {
+                if i & bit == 0 {
+                    self.draw_line(corners[i], corners[i | bit]);
+                }
+            }
+        }
+    }
+
+    pub fn fill_triangle(&mut self, t: Triangle) {
+        self.fill_sides(t, |r, t, _| r.fill_front_face(t));
+    }
+
+    // Calls `fill` with each side of `t` the cull mode may keep, as the
+    // triangle wound to face that way and whether it was flipped.
+    fn fill_sides<F>(&mut self, t: Triangle, mut fill: F)
+        where F: FnMut(&mut Self, Triangle, bool)
+    {
+        let flipped = trigon![t.p1, t.p3, t.p2];
+        match self.cull_mode {
+            CullMode::Back => fill(self, t, false),
+            CullMode::Front => fill(self, flipped, true),
+            CullMode::None => {
+                fill(self, t, false);
+                fill(self, flipped, true);
+            },
+        }
+    }
+
+    // Like `fill_triangle`, but tags the pixels it covers with `id` for
+    // `pick_triangle`.
+    pub fn fill_triangle_with_id(&mut self, t: Triangle, id: usize) {
+        self.texture.set_write_id(Some(id));
+        self.fill_triangle(t);
+        self.texture.set_write_id(None);
+    }
+
+    // The id of the triangle visible at (x, y), if it was drawn with
+    // `fill_triangle_with_id` and nothing untagged has covered it since.
+    pub fn pick_triangle(&self, x: PixCoord, y: PixCoord) -> Option<usize> {
+        self.texture.read_id(x, y)
+    }
+
+    fn fill_front_face(&mut self, t: Triangle) {
+        let ct = t * self.screen_transform();
+        if ct.is_degenerate() { return }
+
+        // Backface culling.
+        let centroid = self.world_centroid(t);
+        if ct.normal().dot(centroid) >= 0. { return }
+
+        let color = self.light_triangle(t, t.normal(), self.color);
+        self.draw_face(t, ct, FaceColor::Flat(color));
+    }
+
+    // Like `fill_triangle`, but culls and lights by `normal`, given in the
+    // same space as `t`, instead of computing the face normal. Culling by it
+    // assumes `t` is in view space, with the camera at the origin.
+    pub fn fill_triangle_with_normal(&mut self, t: Triangle, normal: Point) {
+        self.fill_sides(t, |r, t, flipped| {
+            let normal = if flipped { -normal } else { normal };
+            r.fill_front_face_with_normal(t, normal);
+        });
+    }
+
+    fn fill_front_face_with_normal(&mut self, t: Triangle, normal: Point) {
+        let ct = t * self.screen_transform();
+        if ct.is_degenerate() { return }
+
+        // Backface culling.
+        let centroid = self.world_centroid(t);
+        if self.world_normal(normal).dot(centroid) >= 0. { return }
+
+        let color = self.light_triangle(t, normal, self.color);
+        self.draw_face(t, ct, FaceColor::Flat(color));
+    }
+
+    // Draws a face that survived culling as the render mode asks.
+    fn draw_face(&mut self, t: Triangle, ct: Triangle, color: FaceColor) {
+        if self.render_mode != RenderMode::Wireframe {
+            self.fill_clipped_triangle(t, ct, color);
+        }
+        if self.render_mode != RenderMode::Solid {
+            self.draw_wireframe_overlay(t);
+        }
+    }
+
+    fn draw_wireframe_overlay(&mut self, t: Triangle) {
+        let color = self.wireframe_color;
+        do_with_color!(self, color, {
+            self.draw_triangle(t);
+        });
+    }
+
+    // Fills the parts of `t`, whose transformed version is `ct`, on the kept
+    // side of every clip plane.
+    fn fill_clipped_triangle(
+        &mut self,
+        t: Triangle,
+        ct: Triangle,
+        color: FaceColor
+    ) {
+        let t = t * self.model;
+        if self.clip_planes.is_empty() {
+            self.fill_fogged_triangle(t, ct, color);
+            return;
+        }
+
+        let mut polygon = t.to_arr().to_vec();
+        for &(normal, d) in &self.clip_planes {
+            polygon = clip_polygon(&polygon, normal, d);
+        }
+        if polygon.len() < 3 { return }
+
+        let transform = self.world_screen_transform();
+        for i in 1 .. polygon.len() - 1 {
+            let piece = trigon![polygon[0], polygon[i], polygon[i + 1]];
+            let screen_piece = piece * transform;
+            if screen_piece.is_degenerate() { continue }
+            let piece_color = color.of_piece(t, piece);
+            self.fill_fogged_triangle(piece, screen_piece, piece_color);
+        }
+    }
+
+    // Fills `ct`, the transformed version of `t`, fogged per pixel by the
+    // distance of the matching point of `t` if fog is enabled. `t` is after
+    // the model transform.
+    fn fill_fogged_triangle(
+        &mut self,
+        t: Triangle,
+        ct: Triangle,
+        color: FaceColor
+    ) {
+        let fog = self.fog;
+        if let (None, FaceColor::Flat(color)) = (fog, color) {
+            return self.fill_transformed_triangle(ct, color);
+        }
+        self.rasterize(ct, |b1, b2, b3| {
+            let p = t.p1 * b1 + t.p2 * b2 + t.p3 * b3;
+            let c = color.at(p, b1, b2, b3);
+            match fog {
+                Some(fog) => fog.apply(c, p.magnitude()),
+                None => c,
+            }
+        });
+    }
+
+    // Fills every triangle of the mesh, using its face normals if it has
+    // them.
+    pub fn draw_mesh(&mut self, mesh: &Mesh) {
+        match mesh.normals {
+            Some(ref normals) => {
+                for (&t, &n) in mesh.triangles.iter().zip(normals.iter()) {
+                    self.fill_triangle_with_normal(t, n);
+                }
+            },
+            None => {
+                for &t in &mesh.triangles { self.fill_triangle(t) }
+            },
+        }
+    }
+
+    // Draws the version of `lod` for its distance from `camera_pos`, taken
+    // to the center of its bounding box in the same space.
+    pub fn draw_lod_mesh(&mut self, lod: &LODMesh, camera_pos: Point) {
+        let center = match lod.center() {
+            Some(center) => center,
+            None => return,
+        };
+        if let Some(mesh) = lod.select((center - camera_pos).magnitude()) {
+            self.draw_mesh(mesh);
+        }
+    }
+
+    // Draws `mesh` placed by `model`, applied before the model transform.
+    pub fn draw_mesh_with_transform(&mut self, mesh: &Mesh, model: Transform) {
+        let old_model = self.model;
+        self.model = old_model * model;
+        self.draw_mesh(mesh);
+        self.model = old_model;
+    }
+
+    // Draws a height map as a grid in the XZ plane, `scale` apart, with
+    // heights along Y. `heights` is row-major with `cols * rows` entries;
+    // rows run along Z. Each quad is split into two triangles facing up and
+    // colored from blue at the lowest height to white at the highest.
+    pub fn draw_terrain(
+        &mut self,
+        heights: &[f64],
+        cols: usize,
+        rows: usize,
+        scale: f64
+    ) {
+        if cols < 2 || rows < 2 || heights.len() < cols * rows { return }
+        let heights = &heights[.. cols * rows];
+        let min = heights.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = heights.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let range = if max > min { max - min } else { 1. };
+
+        let point = |c: usize, r: usize| {
+            pt![c as f64 * scale, heights[r * cols + c], r as f64 * scale]
+        };
+        for r in 0 .. rows - 1 {
+            for c in 0 .. cols - 1 {
+                let (p00, p10) = (point(c, r), point(c + 1, r));
+                let (p01, p11) = (point(c, r + 1), point(c + 1, r + 1));
+                let height = (p00.y + p10.y + p01.y + p11.y) / 4.;
+                let t = (height - min) / range;
+                let color = pixel::lerp(pixel::BLUE, pixel::WHITE, t);
+                do_with_color!(self, color, {
+                    self.fill_triangle(trigon![p00, p01, p10]);
+                    self.fill_triangle(trigon![p10, p01, p11]);
+                });
+            }
+        }
+    }
+
+    // Scanline fills a triangle that is already in screen space.
+    fn fill_transformed_triangle(&mut self, ct: Triangle, color: Pixel) {
+        // Sort points by y coord.
+        let mut pts = ct.to_arr();
+        pts.sort_by(
+            |p1, p2|
+            p1.y.partial_cmp(&p2.y)
+                .unwrap_or(Equal)
+        );
+        let (top, middle, bot) = (pts[0], pts[1], pts[2]);
+
+        let old_color = self.color;
+        self.color = self.output_color(color);
+
+        const EPSILON: f64 = 1.;
+        if middle.y - top.y < EPSILON {
+            self.fill_top_flat_triangle(Triangle::from_arr(pts));
+        } else if bot.y - middle.y < EPSILON {
+            self.fill_bottom_flat_triangle(Triangle::from_arr(pts));
+        } else {
+            let dy_mid: Coord = middle.y - top.y;
+            let dy_bot: Coord = bot.y - top.y;
+            let dx_bot: Coord = bot.x - top.x;
+            let dz_bot: Coord = bot.z - top.z;
+
+            let v4 = pt![
+                top.x + dx_bot * dy_mid / dy_bot,
+                middle.y,
+                top.z + dz_bot * dy_mid / dy_bot
+            ];
+            self.fill_bottom_flat_triangle(trigon![top, middle, v4]);
+            self.fill_top_flat_triangle(trigon![middle, v4, bot]);
+        }
+
+        self.color = old_color;
+    }
+
+    // Fills the quad as the triangles (p1, p2, p3) and (p1, p3, p4).
+    pub fn fill_quad(&mut self, p1: Point, p2: Point, p3: Point, p4: Point) {
+        self.fill_triangle(trigon![p1, p2, p3]);
+        self.fill_triangle(trigon![p1, p3, p4]);
+    }
+
+    // Fills a strip of quads sharing edges, as in OpenGL's quad strips: each
+    // pair of points after the first adds the quad (p[2i], p[2i + 1],
+    // p[2i + 3], p[2i + 2]).
+    pub fn fill_quad_strip(&mut self, points: &[Point]) {
+        if points.len() < 4 { return }
+        for i in 0 .. points.len() / 2 - 1 {
+            let p = &points[2 * i ..];
+            self.fill_quad(p[0], p[1], p[3], p[2]);
+        }
+    }
+
+    // Fills one triangle per index triple into the shared `vertices`.
+    pub fn draw_indexed_triangles(
+        &mut self,
+        vertices: &[Point],
+        indices: &[(usize, usize, usize)]
+    ) {
+        for &(i1, i2, i3) in indices {
+            self.fill_triangle(
+                trigon![vertices[i1], vertices[i2], vertices[i3]]
+            );
+        }
+    }
+
+    // Like `draw_indexed_triangles`, but lights each vertex by its entry in
+    // `normals` and interpolates the colors across the triangle (Gouraud
+    // shading). Vertices are only lit in `LightingMode::FlatShading`.
+    pub fn draw_indexed_triangles_with_normals(
+        &mut self,
+        vertices: &[Point],
+        normals: &[Point],
+        indices: &[(usize, usize, usize)]
+    ) {
+        for &(i1, i2, i3) in indices {
+            self.fill_gouraud_triangle(
+                trigon![vertices[i1], vertices[i2], vertices[i3]],
+                [normals[i1], normals[i2], normals[i3]]
+            );
+        }
+    }
+
+    // Gouraud shaded if the vertex buffer has normals, else flat shaded.
+    pub fn draw_geometry(&mut self, vb: &VertexBuffer, ib: &IndexBuffer) {
+        match vb.normals {
+            Some(ref normals) => self.draw_indexed_triangles_with_normals(
+                &vb.vertices,
+                normals,
+                &ib.indices
+            ),
+            None => self.draw_indexed_triangles(&vb.vertices, &ib.indices),
+        }
+    }
+
+    // Culled, clipped and drawn like `fill_triangle`, but lit per vertex.
+    fn fill_gouraud_triangle(&mut self, t: Triangle, normals: [Point; 3]) {
+        self.fill_sides(t, |r, t, flipped| {
+            // Flipping swaps the last two vertices and turns the normals.
+            let [n1, n2, n3] = normals;
+            let normals = if flipped { [-n1, -n3, -n2] } else { normals };
+            r.fill_front_face_gouraud(t, normals);
+        });
+    }
+
+    fn fill_front_face_gouraud(&mut self, t: Triangle, normals: [Point; 3]) {
+        let ct = t * self.screen_transform();
+        if ct.is_degenerate() { return }
+
+        // Backface culling.
+        let centroid = self.world_centroid(t);
+        if ct.normal().dot(centroid) >= 0. { return }
+
+        let color = self.color;
+        let colors = [
+            self.light_vertex(t.p1, normals[0], color),
+            self.light_vertex(t.p2, normals[1], color),
+            self.light_vertex(t.p3, normals[2], color),
+        ];
+        self.draw_face(t, ct, FaceColor::Gouraud(colors));
+    }
+
+    // Lights every pixel of `t` separately by the normal interpolated from
+    // the vertex `normals`, for a diffuse term from `light` in the current
+    // color plus a white Phong highlight seen from `eye`. `light` and `eye`
+    // are positions after the model transform, like the renderer's light.
+    // Culled, clipped and drawn like `fill_triangle`, whatever the lighting
+    // mode.
+    pub fn fill_phong_triangle(
+        &mut self,
+        t: Triangle,
+        normals: [Point; 3],
+        light: Point,
+        eye: Point
+    ) {
+        self.fill_sides(t, |r, t, flipped| {
+            // Flipping swaps the last two vertices and turns the normals.
+            let [n1, n2, n3] = normals;
+            let normals = if flipped { [-n1, -n3, -n2] } else { normals };
+            r.fill_front_face_phong(t, normals, light, eye);
+        });
+    }
+
+    fn fill_front_face_phong(
+        &mut self,
+        t: Triangle,
+        normals: [Point; 3],
+        light: Point,
+        eye: Point
+    ) {
+        let ct = t * self.screen_transform();
+        if ct.is_degenerate() { return }
+
+        // Backface culling.
+        let centroid = self.world_centroid(t);
+        if ct.normal().dot(centroid) >= 0. { return }
+
+        let normal_matrix = self.model.normal_matrix();
+        let face = PhongFace {
+            normals: [
+                normals[0] * normal_matrix,
+                normals[1] * normal_matrix,
+                normals[2] * normal_matrix,
+            ],
+            light: light,
+            eye: eye,
+            color: self.color,
+            ambient: self.ambient,
+            emissive: self.emissive,
+            shininess: self.shininess,
+        };
+        self.draw_face(t, ct, FaceColor::Phong(face));
+    }
+
+    // Fills the triangle with `tex`, mapping each vertex to the matching
+    // (u, v) texture coordinate. Coordinates are interpolated affinely in
+    // screen space. Textured triangles are neither culled nor lit.
+    pub fn fill_textured_triangle(
+        &mut self,
+        t: Triangle,
+        uvs: [(f64, f64); 3],
+        tex: &Texture
+    ) {
+        let ct = t * self.screen_transform();
+        self.rasterize(ct, |b1, b2, b3| {
+            let u = b1 * uvs[0].0 + b2 * uvs[1].0 + b3 * uvs[2].0;
+            let v = b1 * uvs[0].1 + b2 * uvs[1].1 + b3 * uvs[2].1;
+            tex.sample(u, v)
+        });
+    }
+
+    // Maps `tex` onto the quad with its top-left corner at p1, going round
+    // through p2, p3 and p4.
+    pub fn fill_textured_quad(
+        &mut self,
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        p4: Point,
+        tex: &Texture
+    ) {
+        self.fill_textured_triangle(
+            trigon![p1, p2, p3],
+            [(0., 0.), (1., 0.), (1., 1.)],
+            tex
+        );
+        self.fill_textured_triangle(
+            trigon![p1, p3, p4],
+            [(0., 0.), (1., 1.), (0., 1.)],
+            tex
+        );
+    }
+
+    // Like `fill_textured_triangle`, but samples the mipmap chain `mips` at a
+    // level of detail chosen from how many texels of level 0 each covered
+    // pixel spans.
+    pub fn fill_textured_triangle_mipmapped(
+        &mut self,
+        t: Triangle,
+        uvs: [(f64, f64); 3],
+        mips: &[Texture],
+        filter: FilterMode
+    ) {
+        if mips.is_empty() { return }
+        let ct = t * self.screen_transform();
+
+        let (a, b, c) = ct.to_tuple();
+        let pixel_area =
+            ((b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)).abs();
+        let (u1, v1) = uvs[0];
+        let (u2, v2) = uvs[1];
+        let (u3, v3) = uvs[2];
+        let texel_area = ((u2 - u1) * (v3 - v1) - (v2 - v1) * (u3 - u1)).abs()
+            * mips[0].w as f64 * mips[0].h as f64;
+        let lod = if pixel_area > 0. {
+            (0.5 * (texel_area / pixel_area).log2()).max(0.)
+        } else {
+            0.
+        };
+
+        self.rasterize(ct, |b1, b2, b3| {
+            let u = b1 * u1 + b2 * u2 + b3 * u3;
+            let v = b1 * v1 + b2 * v2 + b3 * v3;
+            Texture::sample_mip(mips, u, v, lod, filter)
+        });
+    }
+
+    // Draws `tex` on a quad centered at `center` that lies in the XY plane
+    // of the current transform's frame. With a view-space transform (any
+    // model transform already applied to `center`) the quad always faces the
+    // camera.
+    pub fn draw_billboard(
+        &mut self,
+        center: Point,
+        width: f64,
+        height: f64,
+        tex: &Texture
+    ) {
+        let hw = width  / 2.;
+        let hh = height / 2.;
+        let top_left  = center + pt![-hw, -hh, 0.];
+        let top_right = center + pt![ hw, -hh, 0.];
+        let bot_right = center + pt![ hw,  hh, 0.];
+        let bot_left  = center + pt![-hw,  hh, 0.];
+        self.fill_textured_quad(top_left, top_right, bot_right, bot_left, tex);
+    }
+
+    // Draws a cube-mapped background around the origin, where the camera sits
+    // in view space, behind everything else. `faces` are ordered +X, -X, +Y,
+    // -Y, +Z, -Z and laid out as OpenGL cube maps are.
+    pub fn draw_skybox(&mut self, faces: [&Texture; 6]) {
+        self.draw_skybox_oriented(faces, Transform::identity());
+    }
+
+    // Like `draw_skybox`, but turned by the rotation of the camera's view
+    // transform `view`; its translation is ignored, so the sky stays
+    // centered on the camera.
+    pub fn draw_skybox_oriented(
+        &mut self,
+        faces: [&Texture; 6],
+        view: Transform
+    ) {
+        // Faces are split into tiles to limit the warping of affine texture
+        // mapping, and clipped just in front of the camera.
+        const TILES: usize = 8;
+        const NEAR: f64 = 1e-3;
+        // As far as a depth can go while staying finite when interpolated.
+        const DEPTH: f64 = f64::MAX / 2.;
+
+        let mut rotation = view.to_array();
+        for row in rotation.iter_mut().take(3) { row[3] = 0. }
+        let rotation = Transform::from_array(rotation);
+        let unrotation = rotation.inverse().unwrap_or_else(Transform::identity);
+        let screen_transform = self.world_screen_transform();
+
+        for (face, tex) in faces.iter().enumerate() {
+            for j in 0 .. TILES {
+                for i in 0 .. TILES {
+                    let u0 = i as f64 / TILES as f64;
+                    let v0 = j as f64 / TILES as f64;
+                    let u1 = (i + 1) as f64 / TILES as f64;
+                    let v1 = (j + 1) as f64 / TILES as f64;
+                    let tile: Vec<Point> =
+                        [(u0, v0), (u1, v0), (u1, v1), (u0, v1)].iter()
+                            .map(|&(u, v)| skybox_point(face, u, v) * rotation)
+                            .collect();
+                    let tile = clip_polygon(&tile, pt![0., 0., -1.], -NEAR);
+                    if tile.len() < 3 { continue }
+
+                    let uvs: Vec<(f64, f64)> = tile.iter()
+                        .map(|&p| skybox_uv(face, p * unrotation))
+                        .collect();
+                    for k in 1 .. tile.len() - 1 {
+                        let mut ct = trigon![tile[0], tile[k], tile[k + 1]]
+                            * screen_transform;
+                        ct.p1.z = DEPTH;
+                        ct.p2.z = DEPTH;
+                        ct.p3.z = DEPTH;
+                        let tri_uvs = [uvs[0], uvs[k], uvs[k + 1]];
+                        self.rasterize(ct, |b1, b2, b3| {
+                            let u = b1 * tri_uvs[0].0
+                                + b2 * tri_uvs[1].0
+                                + b3 * tri_uvs[2].0;
+                            let v = b1 * tri_uvs[0].1
+                                + b2 * tri_uvs[1].1
+                                + b3 * tri_uvs[2].1;
+                            tex.sample(u, v)
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // Rasterizes a screen-space triangle by testing every pixel center in its
+    // bounding box. `shade` receives the barycentric weights of the pixel
+    // with respect to p1, p2 and p3 and returns its color.
+    fn rasterize<F>(&mut self, ct: Triangle, mut shade: F)
+        where F: FnMut(f64, f64, f64) -> Pixel
+    {
+        let (a, b, c) = ct.to_tuple();
+        let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+        if area == 0. { return }
+
+        let edge = |p: Point, q: Point, x: f64, y: f64| {
+            ((q.x - p.x) * (y - p.y) - (q.y - p.y) * (x - p.x)) / area
+        };
+
+        let max_x = (self.texture.w as f64 - 1.).max(0.);
+        let max_y = (self.texture.h as f64 - 1.).max(0.);
+        let x_min = a.x.min(b.x).min(c.x).floor().max(0.) as PixCoord;
+        let x_max = a.x.max(b.x).max(c.x).ceil().min(max_x) as PixCoord;
+        let y_min = a.y.min(b.y).min(c.y).floor().max(0.) as PixCoord;
+        let y_max = a.y.max(b.y).max(c.y).ceil().min(max_y) as PixCoord;
+
+        for y in y_min .. y_max + 1 {
+            for x in x_min .. x_max + 1 {
+                let px = x as f64 + 0.5;
+                let py = y as f64 + 0.5;
+                let b1 = edge(b, c, px, py);
+                let b2 = edge(c, a, px, py);
+                let b3 = edge(a, b, px, py);
+                if b1 < 0. || b2 < 0. || b3 < 0. { continue }
+
+                let z = b1 * a.z + b2 * b.z + b3 * c.z;
+                let color = self.output_color(shade(b1, b2, b3));
+                self.texture.set_pixel(x, y, z, color);
+            }
+        }
+    }
+
+    // Draws `text` in screen space with its top-left corner at (x, y), on top
+    // of everything else. '\n' starts a new line.
+    pub fn draw_text(&mut self, text: &str, x: PixCoord, y: PixCoord) {
+        let size = font::GLYPH_SIZE as i64;
+        let color = self.output_color(self.color);
+        let (w, h) = (self.texture.w as i64, self.texture.h as i64);
+        // Positions are tracked wider than PixCoord so long lines don't
+        // overflow it.
+        let mut cx = x as i64;
+        let mut cy = y as i64;
+        for c in text.chars() {
+            if c == '\n' {
+                cx = x as i64;
+                cy += size;
+                continue;
+            }
+            // Nothing more lands on the texture once below it, nor right of
+            // it until the next line.
+            if cy >= h { break }
+            if cx >= w { continue }
+
+            let glyph = self.font.glyph(c);
+            for gy in 0 .. font::GLYPH_SIZE {
+                for gx in 0 .. font::GLYPH_SIZE {
+                    if !BitmapFont::glyph_pixel(glyph, gx, gy) { continue }
+                    let (px, py) = (cx + gx as i64, cy + gy as i64);
+                    if px < 0 || py < 0 || px >= w || py >= h { continue }
+                    self.texture.set_pixel(
+                        px as PixCoord,
+                        py as PixCoord,
+                        f64::NEG_INFINITY,
+                        color
+                    );
+                }
+            }
+            cx += size;
+        }
+    }
+
+    // The width and height in pixels of the box `draw_text` fills for
+    // `text`. Glyphs sit edge to edge, their own blank columns spacing them,
+    // so each line is as wide as its characters and as tall as a glyph.
+    pub fn measure_text(&self, text: &str) -> (Dimension, Dimension) {
+        if text.is_empty() { return (0, 0) }
+        let lines = text.split('\n');
+        let (count, longest) = lines.fold((0, 0), |(count, longest), line| {
+            (count + 1, longest.max(line.chars().count()))
+        });
+        let size = font::GLYPH_SIZE;
+        (longest as Dimension * size, count as Dimension * size)
+    }
+
+    // Like `draw_text`, with the top-left corner of the text where `pos`
+    // lands on screen. Nothing is drawn for positions behind the camera or
+    // outside the viewport, and when `depth_test` is set, nor for positions
+    // hidden behind what has already been drawn. The text itself goes on top
+    // of everything else.
+    pub fn draw_text_3d(&mut self, text: &str, pos: Point, depth_test: bool) {
+        let w = homogeneous_w(pos, self.transform * self.model);
+        if w <= 0. { return }
+        let p = pos * self.screen_transform();
+        let (vx, vy, vw, vh) = self.viewport_rect();
+        let inside = p.x >= vx as f64 && p.x < vx as f64 + vw as f64
+            && p.y >= vy as f64 && p.y < vy as f64 + vh as f64;
+        if !inside { return }
+        let (x, y) = (p.x.floor() as PixCoord, p.y.floor() as PixCoord);
+        if depth_test && !self.texture.depth_passes(x, y, p.z) { return }
+        self.draw_text(text, x, y);
+    }
+
+    // Copies `sprite` into screen space with its top-left corner at (x, y),
+    // on top of everything else.
+    pub fn draw_sprite(
+        &mut self,
+        sprite: &TextureView,
+        x: PixCoord,
+        y: PixCoord
+    ) {
+        // Only the part of the sprite over the texture is visited, worked out
+        // wider than PixCoord so sprites near its limits don't overflow it.
+        let (x, y) = (x as i64, y as i64);
+        let (w, h) = (self.texture.w as i64, self.texture.h as i64);
+        let xs = (-x).max(0) .. (w - x).min(sprite.w as i64);
+        let ys = (-y).max(0) .. (h - y).min(sprite.h as i64);
+        for sy in ys {
+            for sx in xs.clone() {
+                let texel = sprite.pixel(sx as Dimension, sy as Dimension);
+                let color = self.output_color(texel);
+                self.texture.set_pixel(
+                    (x + sx) as PixCoord,
+                    (y + sy) as PixCoord,
+                    f64::NEG_INFINITY,
+                    color
+                );
+            }
+        }
+    }
+
+    // Lights `t` by `normal`, both given before the model transform.
+    fn light_triangle(&self, t: Triangle, normal: Point, color: Pixel)
+        -> Pixel
+    {
+        let lit = match self.lighting_mode {
+            LightingMode::NoShading => color,
+            LightingMode::FlatShading => {
+                let light_dir = (self.light - self.world_centroid(t))
+                    .normalized();
+                self.shade(color, light_dir.dot(self.world_normal(normal)))
+            },
+        };
+        pixel::blend_additive(lit, self.emissive)
+    }
+
+    fn light_vertex(&self, p: Point, normal: Point, color: Pixel) -> Pixel {
+        let lit = match self.lighting_mode {
+            LightingMode::NoShading => color,
+            LightingMode::FlatShading => {
+                let light_dir = (self.light - p * self.model).normalized();
+                self.shade(color, light_dir.dot(self.world_normal(normal)))
+            },
+        };
+        pixel::blend_additive(lit, self.emissive)
+    }
+
+    // The center of `t` after the model transform.
+    fn world_centroid(&self, t: Triangle) -> Point {
+        (t.p1 + t.p2 + t.p3) * (1. / 3.) * self.model
+    }
+
+    // `normal` carried through the model transform, at unit length.
+    fn world_normal(&self, normal: Point) -> Point {
+        (normal * self.model.normal_matrix()).normalized()
+    }
+
+    // Scales `color` by the diffuse term `cos_angle`, raised by the ambient
+    // fraction so unlit faces keep some color.
+    fn shade(&self, color: Pixel, cos_angle: f64) -> Pixel {
+        let light_mag = self.ambient + (1. - self.ambient) * cos_angle.max(0.);
+        let (r, g, b) = color;
+        (
+            (r as f64 * light_mag) as u8,
+            (g as f64 * light_mag) as u8,
+            (b as f64 * light_mag) as u8
+        )
+    }
+
+    // Applies color modes that affect every pixel written to the texture.
+    fn output_color(&self, color: Pixel) -> Pixel {
+        if self.grayscale { pixel::to_grayscale(color) } else { color }
+    }
+
+    fn fill_bottom_flat_triangle(&mut self, t: Triangle) {
+        let (top, mut left, mut right) = t.to_tuple();
+        if left.x > right.x { mem::swap(&mut left, &mut right) }
+
+        for y in top.y as PixCoord .. left.y as PixCoord {
+            let t = (y - top.y as PixCoord) as Coord / (left.y - top.y);
+
+            let z_left  = top.z + t * (left.z  - top.z);
+            let z_right = top.z + t * (right.z - top.z);
+
+            self.texture.set_row(
+                (top.x + (left.x  - top.x) * t) as PixCoord,
+                (top.x + (right.x - top.x) * t) as PixCoord,
+                y,
+                z_left,
+                z_right,
+                self.color
+            );
+        }
+    }
+
+    fn fill_top_flat_triangle(&mut self, t: Triangle) {
+        let (mut left, mut right, bot) = t.to_tuple();
+        if left.x > right.x { mem::swap(&mut left, &mut right) }
+
+        for y in left.y as PixCoord .. bot.y as PixCoord + 1 {
+            let t       = (y - left.y as PixCoord) as Coord / (bot.y - left.y);
+            let z_left  = left.z  + t * (bot.z - left.z);
+            let z_right = right.z + t * (bot.z - right.z);
+
+            self.texture.set_row(
+                (left.x  + (bot.x - left.x)  * t) as PixCoord,
+                (right.x + (bot.x - right.x) * t) as PixCoord,
+                y,
+                z_left,
+                z_right,
+                self.color
+            );
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.texture.clear();
+        if self.clear_color != pixel::BLACK {
+            self.texture.set_all_pixels(self.clear_color);
+        }
+    }
+
+    pub fn clear_depth(&mut self) {
+        self.texture.clear_depth();
+    }
+
+    // Clears like `clear`, then paints a vertical gradient running from `top`
+    // on the first row to `bottom` on the last.
+    pub fn clear_with_gradient(&mut self, top: Pixel, bottom: Pixel) {
+        self.clear();
+        let h = self.texture.h;
+        for y in 0 .. h {
+            let color = pixel::lerp(top, bottom, gradient_t(y, h));
+            let row = self.texture.pixels_mut_row_slice(y as PixCoord);
+            if let Some(row) = row {
+                for p in row.iter_mut() {
+                    *p = color;
+                }
+            }
+        }
+        self.texture.sync_hdr();
+    }
+
+    // Like `clear_with_gradient`, but runs from `left` on the first column to
+    // `right` on the last.
+    pub fn clear_with_gradient_h(&mut self, left: Pixel, right: Pixel) {
+        self.clear();
+        let w = self.texture.w;
+        let colors: Vec<Pixel> = (0 .. w)
+            .map(|x| pixel::lerp(left, right, gradient_t(x, w)))
+            .collect();
+        for y in 0 .. self.texture.h {
+            let row = self.texture.pixels_mut_row_slice(y as PixCoord);
+            if let Some(row) = row {
+                row.copy_from_slice(&colors);
+            }
+        }
+        self.texture.sync_hdr();
+    }
+
+    // Shows the screen's frame, even while a render target is set.
+    pub fn display(&mut self) -> Result<(), Box<error::Error>> {
+        self.with_screen_texture(|r| r.display_frame())
+    }
+
+    fn display_frame(&mut self) -> Result<(), Box<error::Error>> {
+        if self.post_processes.is_empty()
+            && !self.gamma_correction
+            && self.texture.hdr().is_none()
+        {
+            return self.screen.display_texture(&self.texture);
+        }
+
+        // Run the passes on a copy so the rendered frame is left untouched.
+        // Tone mapping keeps the depths, which passes such as SSAO read.
+        let mut src = self.texture.tonemapped();
+        if !self.post_processes.is_empty() {
+            let mut dst = self.texture.clone();
+            for pp in &self.post_processes {
+                pp.apply(&src, &mut dst);
+                mem::swap(&mut src, &mut dst);
+            }
+        }
+        if self.gamma_correction { src = src.to_srgb(); }
+        self.screen.display_texture(&src)
+    }
+
+    pub fn resize(&mut self, w: Dimension, h: Dimension)
+        -> Result<(), Box<error::Error>>
+    {
+        if w as u64 * h as u64 > MAX_TEXTURE_PIXELS as u64 {
+            return Err(From::from(format!(
+                "{}x{} is larger than the biggest texture allowed",
+                w, h
+            )));
+        }
+        try!(self.screen.resize(w, h));
+        self.with_screen_texture(|r| r.texture.resize(w, h));
+        Ok(())
+    }
+
+    // Sends everything drawn from now on to `target` instead of the screen,
+    // until `unset_render_target`. The transform and viewport are kept, so
+    // set the viewport to fit a target of another size. None goes back to
+    // drawing to the screen, dropping the current target.
+    pub fn set_render_target(&mut self, target: Option<Texture>) {
+        match target {
+            Some(target) => {
+                let old = mem::replace(&mut self.texture, target);
+                if self.screen_texture.is_none() {
+                    self.screen_texture = Some(old);
+                }
+            },
+            None => {
+                if let Some(frame) = self.screen_texture.take() {
+                    self.texture = frame;
+                }
+            },
+        }
+    }
+
+    // Goes back to drawing to the screen, handing back what was drawn to the
+    // render target. None if no target was set.
+    pub fn unset_render_target(&mut self) -> Option<Texture> {
+        self.screen_texture
+            .take()
+            .map(|frame| mem::replace(&mut self.texture, frame))
+    }
+
+    // Runs `f` with the screen's frame in place of any render target.
+    fn with_screen_texture<T, F>(&mut self, f: F) -> T
+        where F: FnOnce(&mut Self) -> T
+    {
+        if let Some(ref mut frame) = self.screen_texture {
+            mem::swap(frame, &mut self.texture);
+        }
+        let result = f(self);
+        if let Some(ref mut frame) = self.screen_texture {
+            mem::swap(frame, &mut self.texture);
+        }
+        result
+    }
+
+    // A copy of the frame rendered so far, before any display passes. While a
+    // render target is set, a copy of the target.
+    pub fn snapshot(&self) -> Texture { self.texture.clone() }
+
+    pub fn screen(&self) -> &S { &self.screen }
+    pub fn screen_mut(&mut self) -> &mut S { &mut self.screen }
+
+    pub fn add_post_process(&mut self, pp: Box<PostProcess>) {
+        self.post_processes.push(pp);
+    }
+
+    pub fn set_post_processes(&mut self, pps: Vec<Box<PostProcess>>) {
+        self.post_processes = pps;
+    }
+
+    pub fn clear_post_processes(&mut self) {
+        self.post_processes.clear();
+    }
+
+
+    // Renders into the rectangle (x, y, w, h) of the frame only. While a
+    // viewport is set, the current transform is taken to produce normalized
+    // coordinates in [-1, 1], which are mapped onto the viewport with y
+    // pointing down. Without one, the transform maps straight to pixels.
+    pub fn set_viewport(
+        &mut self,
+        x: PixCoord,
+        y: PixCoord,
+        w: Dimension,
+        h: Dimension
+    ) {
+        self.viewport = Some((x, y, w, h));
+        self.texture.set_scissor(self.viewport);
+    }
+
+    pub fn clear_viewport(&mut self) {
+        self.viewport = None;
+        self.texture.set_scissor(None);
+    }
+
+    // The viewport, or the whole texture without one.
+    fn viewport_rect(&self) -> (PixCoord, PixCoord, Dimension, Dimension) {
+        self.viewport.unwrap_or((0, 0, self.texture.w, self.texture.h))
+    }
+
+    // The model transform, then the current transform and the viewport
+    // mapping.
+    fn screen_transform(&self) -> Transform {
+        self.world_screen_transform() * self.model
+    }
+
+    // The current transform followed by the viewport mapping, if any.
+    fn world_screen_transform(&self) -> Transform {
+        match self.viewport {
+            Some((x, y, w, h)) => {
+                let (hw, hh) = (w as f64 / 2., h as f64 / 2.);
+                Transform::translate(pt_2d![x as f64 + hw, y as f64 + hh])
+                    * Transform::scale(hw, hh, 1.)
+                    * self.transform
+            },
+            None => self.transform,
+        }
+    }
+
+    pub fn set_transform(&mut self, t: Transform) {
+        self.transform = t;
+    }
+
+    pub fn clear_transform(&mut self) {
+        self.transform = Transform::identity();
+    }
+
+    // Places everything drawn afterwards by `model`, applied before the
+    // current transform. The light, fog distances and clip planes stay in
+    // the space `model` maps into, and normals follow `model.normal_matrix()`,
+    // so lighting stays right under non-uniform scale.
+    pub fn set_model_transform(&mut self, model: Transform) {
+        self.model = model;
+    }
+
+    pub fn clear_model_transform(&mut self) {
+        self.model = Transform::identity();
+    }
+
+    pub fn translate(&mut self, p: Point) {
+        self.transform = Transform::translate(p) * self.transform;
+    }
+
+    pub fn rotate_x(&mut self, theta: f64) {
+        self.transform = Transform::rotate_x(theta) * self.transform;
+    }
+
+    pub fn rotate_y(&mut self, theta: f64) {
+        self.transform = Transform::rotate_y(theta) * self.transform;
+    }
+
+    pub fn rotate_z(&mut self, theta: f64) {
+        self.transform = Transform::rotate_z(theta) * self.transform;
+    }
+
+    pub fn rotate_axis_angle(&mut self, axis: Point, angle: f64) {
+        self.transform =
+            Transform::rotate_axis_angle(axis, angle) * self.transform;
+    }
 
     pub fn scale(&mut self, x: f64, y: f64, z: f64) {
         self.transform = Transform::scale(x, y, z) * self.transform;
     }
 
-    pub fn perspective(&mut self) {
-        self.transform = Transform::perspective() * self.transform;
+    pub fn scale_uniform(&mut self, s: f64) {
+        self.transform = Transform::scale_uniform(s) * self.transform;
+    }
+
+    pub fn shear(
+        &mut self,
+        xy: f64,
+        xz: f64,
+        yx: f64,
+        yz: f64,
+        zx: f64,
+        zy: f64
+    ) {
+        self.transform =
+            Transform::shear(xy, xz, yx, yz, zx, zy) * self.transform;
+    }
+
+    // Mirroring reverses the winding of filled triangles on screen, so draw
+    // mirrored geometry with `CullMode::Front` to keep the same faces.
+    pub fn reflect(&mut self, plane_normal: Point) {
+        self.transform = Transform::reflect(plane_normal) * self.transform;
+    }
+
+    pub fn perspective(&mut self) {
+        self.transform = Transform::perspective() * self.transform;
+    }
+
+    // `Transform::perspective_opengl` with the screen's aspect ratio.
+    pub fn perspective_fov(&mut self, fov: f64, near: f64, far: f64) {
+        let aspect = self.screen.aspect_ratio();
+        self.transform =
+            Transform::perspective_opengl(fov, aspect, near, far)
+            * self.transform;
+    }
+
+
+    pub fn set_color(&mut self, color: Pixel) { self.color = color; }
+
+    // Saves the current color, to be restored by the matching `pop_color`.
+    pub fn push_color(&mut self) {
+        self.color_stack.push(self.color);
+    }
+
+    pub fn pop_color(&mut self) {
+        if let Some(color) = self.color_stack.pop() { self.color = color; }
+    }
+
+    // Saves the current state, which is restored when the returned guard is
+    // dropped. The guard derefs to the renderer, so drawing continues
+    // through it:
+    //
+    //     {
+    //         let mut r = renderer.push_state();
+    //         r.translate(offset);
+    //         r.draw_mesh(&mesh);
+    //     }
+    pub fn push_state<'a>(&'a mut self) -> RendererGuard<'a, S> {
+        let state = RendererState {
+            transform: self.transform,
+            model: self.model,
+            viewport: self.viewport,
+            color: self.color,
+
+            light: self.light,
+            lighting_mode: self.lighting_mode,
+            ambient: self.ambient,
+            emissive: self.emissive,
+            shininess: self.shininess,
+            fog: self.fog,
+            point_size: self.point_size,
+            clear_color: self.clear_color,
+
+            gamma_correction: self.gamma_correction,
+            grayscale: self.grayscale,
+            cull_mode: self.cull_mode,
+            render_mode: self.render_mode,
+            wireframe_color: self.wireframe_color,
+            line_antialiasing: self.line_antialiasing,
+            line_width: self.line_width,
+            clip_planes: self.clip_planes.clone(),
+
+            font: self.font.clone(),
+            blend_mode: self.texture.blend_mode(),
+            depth_test: self.texture.depth_test(),
+        };
+        self.state_stack.push(state);
+        RendererGuard { renderer: self }
+    }
+
+    fn pop_state(&mut self) {
+        let state = match self.state_stack.pop() {
+            Some(state) => state,
+            None => return,
+        };
+        self.transform = state.transform;
+        self.model = state.model;
+        self.viewport = state.viewport;
+        self.texture.set_scissor(state.viewport);
+        self.color = state.color;
+
+        self.light = state.light;
+        self.lighting_mode = state.lighting_mode;
+        self.ambient = state.ambient;
+        self.emissive = state.emissive;
+        self.shininess = state.shininess;
+        self.fog = state.fog;
+        self.point_size = state.point_size;
+        self.clear_color = state.clear_color;
+
+        self.gamma_correction = state.gamma_correction;
+        self.grayscale = state.grayscale;
+        self.cull_mode = state.cull_mode;
+        self.render_mode = state.render_mode;
+        self.wireframe_color = state.wireframe_color;
+        self.line_antialiasing = state.line_antialiasing;
+        self.line_width = state.line_width;
+        self.clip_planes = state.clip_planes;
+
+        self.font = state.font;
+        self.texture.set_blend_mode(state.blend_mode);
+        self.texture.set_depth_test(state.depth_test);
+    }
+
+    pub fn set_font(&mut self, font: BitmapFont) { self.font = font; }
+    pub fn set_light_pos(&mut self, pos: Point) { self.light = pos; }
+    pub fn set_lighting_mode(&mut self, lighting_mode: LightingMode) {
+        self.lighting_mode = lighting_mode;
+    }
+    pub fn set_gamma_correction(&mut self, enabled: bool) {
+        self.gamma_correction = enabled;
+    }
+
+    // When HDR is enabled the frame is also rasterized into a floating-point
+    // buffer, where blending may go past white, and that buffer is tone
+    // mapped for display.
+    pub fn set_hdr(&mut self, enabled: bool) {
+        self.with_screen_texture(|r| r.texture.set_hdr(enabled));
+    }
+
+    pub fn set_grayscale_mode(&mut self, enabled: bool) {
+        self.grayscale = enabled;
     }
 
+    // When enabled, the edges of every filled triangle are drawn over it in
+    // the wireframe color. Shorthand for `set_render_mode`.
+    pub fn set_wireframe_overlay(&mut self, enabled: bool) {
+        self.render_mode = if enabled {
+            RenderMode::SolidWireframe
+        } else {
+            RenderMode::Solid
+        };
+    }
 
-    pub fn set_color(&mut self, color: Pixel) { self.color = color; }
-    pub fn set_light_pos(&mut self, pos: Point) { self.light = pos; }
-    pub fn set_lighting_mode(&mut self, lighting_mode: LightingMode) {
-        self.lighting_mode = lighting_mode;
+    pub fn set_render_mode(&mut self, mode: RenderMode) {
+        self.render_mode = mode;
+    }
+
+    // When enabled, `draw_line` draws through `draw_line_aa`.
+    pub fn set_line_antialiasing(&mut self, enabled: bool) {
+        self.line_antialiasing = enabled;
+    }
+
+    // The width in pixels of lines drawn by `draw_line`, wireframes and
+    // outlines. Antialiased lines stay a pixel wide.
+    pub fn set_line_width(&mut self, pixels: f64) {
+        self.line_width = pixels;
+    }
+
+    pub fn set_wireframe_color(&mut self, color: Pixel) {
+        self.wireframe_color = color;
+    }
+
+    // When enabled, filled triangles are also drawn with reversed winding,
+    // so they show from both sides. Shorthand for `set_cull_mode`.
+    pub fn set_double_sided(&mut self, enabled: bool) {
+        self.cull_mode = if enabled { CullMode::None } else { CullMode::Back };
+    }
+
+    pub fn set_cull_mode(&mut self, mode: CullMode) {
+        self.cull_mode = mode;
+    }
+
+    pub fn set_depth_test(&mut self, mode: DepthTest) {
+        self.texture.set_depth_test(mode);
+    }
+
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.texture.set_blend_mode(mode);
+    }
+
+    // Fraction of a lit color kept even where no light reaches, in [0, 1].
+    pub fn set_ambient(&mut self, ambient: f64) {
+        self.ambient = ambient;
+    }
+
+    // Added to the lit color of filled triangles, so they glow whatever the
+    // light does.
+    pub fn set_emissive(&mut self, color: Pixel) {
+        self.emissive = color;
+    }
+
+    // The Phong exponent of the highlights drawn by `fill_phong_triangle`;
+    // higher values give smaller, sharper highlights.
+    pub fn set_shininess(&mut self, shininess: f64) {
+        self.shininess = shininess;
+    }
+
+    // The side in pixels of points drawn by `draw_point`; see
+    // `draw_point_with_size`.
+    pub fn set_point_size(&mut self, px: f64) {
+        self.point_size = px;
+    }
+
+    // Fogs filled triangles, or stops fogging them with None.
+    pub fn set_fog(&mut self, fog: Option<Fog>) {
+        self.fog = fog;
+    }
+
+    // Sets the density of the current fog, or starts exponential fog in black
+    // if there is none.
+    pub fn set_fog_density(&mut self, density: f64) {
+        match self.fog {
+            Some(ref mut fog) => fog.density = density,
+            None => self.fog = Some(Fog::exp(pixel::BLACK, density)),
+        }
+    }
+
+    // The color `clear` fills the frame with.
+    pub fn set_clear_color(&mut self, color: Pixel) {
+        self.clear_color = color;
+    }
+
+    // The screen frame's HDR buffer, for writing overbright values directly.
+    pub fn hdr_buffer_mut(&mut self) -> Option<&mut TextureHdr> {
+        match self.screen_texture {
+            Some(ref mut frame) => frame.hdr_mut(),
+            None => self.texture.hdr_mut(),
+        }
+    }
+
+    // Keeps only the parts of filled triangles where normal . p + d >= 0,
+    // with p after the model transform and before the current transform.
+    // Planes add up; all of them must hold.
+    pub fn add_clip_plane(&mut self, normal: Point, d: f64) {
+        self.clip_planes.push((normal, d));
+    }
+
+    pub fn clear_clip_planes(&mut self) {
+        self.clip_planes.clear();
+    }
+}
+
+
+// Restores the state saved by `Renderer::push_state` when dropped.
+pub struct RendererGuard<'a, S>
+    where S: Screen + 'a
+{
+    renderer: &'a mut Renderer<S>,
+}
+
+impl<'a, S> Deref for RendererGuard<'a, S>
+    where S: Screen
+{
+    type Target = Renderer<S>;
+
+    fn deref(&self) -> &Renderer<S> { self.renderer }
+}
+
+impl<'a, S> DerefMut for RendererGuard<'a, S>
+    where S: Screen
+{
+    fn deref_mut(&mut self) -> &mut Renderer<S> { self.renderer }
+}
+
+impl<'a, S> Drop for RendererGuard<'a, S>
+    where S: Screen
+{
+    fn drop(&mut self) {
+        self.renderer.pop_state();
+    }
+}
+
+
+// Collects construction-time options for a `Renderer`. Options left unset
+// keep the defaults of `Renderer::new`.
+pub struct RendererBuilder {
+    ambient: f64,
+    clear_color: Pixel,
+    cull_mode: CullMode,
+    render_mode: RenderMode,
+    blend_mode: BlendMode,
+}
+
+impl Default for RendererBuilder {
+    fn default() -> RendererBuilder {
+        RendererBuilder {
+            ambient: 0.,
+            clear_color: pixel::BLACK,
+            cull_mode: CullMode::Back,
+            render_mode: RenderMode::Solid,
+            blend_mode: BlendMode::Replace,
+        }
+    }
+}
+
+impl RendererBuilder {
+    pub fn new() -> RendererBuilder {
+        RendererBuilder::default()
+    }
+
+    pub fn with_ambient(mut self, ambient: f64) -> RendererBuilder {
+        self.ambient = ambient;
+        self
+    }
+
+    pub fn with_clear_color(mut self, color: Pixel) -> RendererBuilder {
+        self.clear_color = color;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, mode: CullMode) -> RendererBuilder {
+        self.cull_mode = mode;
+        self
+    }
+
+    pub fn with_render_mode(mut self, mode: RenderMode) -> RendererBuilder {
+        self.render_mode = mode;
+        self
+    }
+
+    pub fn with_blend_mode(mut self, mode: BlendMode) -> RendererBuilder {
+        self.blend_mode = mode;
+        self
+    }
+
+    pub fn build<S: Screen>(self, screen: S) -> Renderer<S> {
+        let mut renderer = Renderer::new(screen);
+        renderer.set_ambient(self.ambient);
+        renderer.set_clear_color(self.clear_color);
+        renderer.set_cull_mode(self.cull_mode);
+        renderer.set_render_mode(self.render_mode);
+        renderer.set_blend_mode(self.blend_mode);
+        renderer.clear();
+        renderer
+    }
+}
+
+// The point at (u, v) in [0, 1]^2 of the bicubic Bezier surface over `ctrl`,
+// with u running along the outer index and v along the inner one.
+pub fn bezier_surface_point(ctrl: [[Point; 4]; 4], u: f64, v: f64) -> Point {
+    let bernstein = |t: f64| {
+        let s = 1. - t;
+        [s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t]
+    };
+    let (bu, bv) = (bernstein(u), bernstein(v));
+    let mut p = pt![0., 0., 0.];
+    for (row, &wu) in ctrl.iter().zip(bu.iter()) {
+        for (&c, &wv) in row.iter().zip(bv.iter()) {
+            p = p + c * (wu * wv);
+        }
+    }
+    p
+}
+
+// The point of the unit cube's face `face` (ordered +X, -X, +Y, -Y, +Z, -Z)
+// at texture coordinates (u, v), following the OpenGL cube map layout.
+fn skybox_point(face: usize, u: f64, v: f64) -> Point {
+    let (s, t) = (2. * u - 1., 2. * v - 1.);
+    match face {
+        0 => pt![ 1., -t, -s],
+        1 => pt![-1., -t,  s],
+        2 => pt![ s,  1.,  t],
+        3 => pt![ s, -1., -t],
+        4 => pt![ s, -t,  1.],
+        _ => pt![-s, -t, -1.],
+    }
+}
+
+// The inverse of `skybox_point` for a point `p` on the face.
+fn skybox_uv(face: usize, p: Point) -> (f64, f64) {
+    let (s, t) = match face {
+        0 => (-p.z / p.x, -p.y / p.x),
+        1 => ( p.z / -p.x, -p.y / -p.x),
+        2 => ( p.x / p.y,  p.z / p.y),
+        3 => ( p.x / -p.y, -p.z / -p.y),
+        4 => ( p.x / p.z, -p.y / p.z),
+        _ => (-p.x / -p.z, -p.y / -p.z),
+    };
+    ((s + 1.) / 2., (t + 1.) / 2.)
+}
+
+// The w coordinate `p` has after `transform`, before the perspective divide.
+// This is 1 for affine transforms.
+fn homogeneous_w(p: Point, transform: Transform) -> f64 {
+    let row = transform.to_array()[3];
+    row[0] * p.x + row[1] * p.y + row[2] * p.z + row[3]
+}
+
+// The side in whole pixels of a particle `size` pixels across.
+fn particle_side(size: f64) -> Dimension {
+    size.round().max(1.) as Dimension
+}
+
+// Where step `i` of `n` falls between the two ends of a gradient.
+fn gradient_t(i: Dimension, n: Dimension) -> f64 {
+    if n > 1 { i as f64 / (n - 1) as f64 } else { 0. }
+}
+
+// The barycentric coordinates of `p`, taken to lie in the plane of `t`.
+fn barycentric(t: Triangle, p: Point) -> (f64, f64, f64) {
+    let n = (t.p2 - t.p1).cross(t.p3 - t.p1);
+    let area = n.dot(n);
+    if area == 0. { return (1., 0., 0.) }
+    let b1 = (t.p3 - t.p2).cross(p - t.p2).dot(n) / area;
+    let b2 = (t.p1 - t.p3).cross(p - t.p3).dot(n) / area;
+    (b1, b2, 1. - b1 - b2)
+}
+
+// Clips a convex polygon to the half-space normal . p + d >= 0
+// (Sutherland-Hodgman).
+fn clip_polygon(polygon: &[Point], normal: Point, d: f64) -> Vec<Point> {
+    let mut out = Vec::with_capacity(polygon.len() + 1);
+    for i in 0 .. polygon.len() {
+        let a = polygon[i];
+        let b = polygon[(i + 1) % polygon.len()];
+        let da = normal.dot(a) + d;
+        let db = normal.dot(b) + d;
+        if da >= 0. { out.push(a) }
+        if (da >= 0.) != (db >= 0.) {
+            let t = da / (da - db);
+            out.push(a + (b - a) * t);
+        }
+    }
+    out
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use screen::SliceFormat;
+    use screen::SliceScreen;
+    use screen::TextScreen;
+
+    fn renderer(w: Dimension, h: Dimension) -> Renderer<TextScreen> {
+        Renderer::new(TextScreen::new("", w, h))
+    }
+
+    fn lit_pixels(r: &Renderer<TextScreen>) -> usize {
+        r.snapshot().pixels.iter().filter(|&&p| p != pixel::BLACK).count()
+    }
+
+    // Renders with `draw` into a w x h RGB buffer, displays it and returns
+    // the bytes that reached the screen.
+    fn displayed<F>(w: Dimension, h: Dimension, draw: F) -> Vec<u8>
+        where F: FnOnce(&mut Renderer<SliceScreen>)
+    {
+        let mut buf = vec![0; 3 * w as usize * h as usize];
+        {
+            let stride = 3 * w as usize;
+            let screen =
+                SliceScreen::new(&mut buf, w, h, stride, SliceFormat::Rgb)
+                    .unwrap();
+            let mut r = Renderer::new(screen);
+            draw(&mut r);
+            r.display().unwrap();
+        }
+        buf
+    }
+
+    #[test]
+    fn gamma_correction_encodes_mid_gray() {
+        let draw = |r: &mut Renderer<SliceScreen>| {
+            r.set_clear_color((128, 128, 128));
+            r.clear();
+        };
+        assert_eq!(&displayed(2, 2, draw)[.. 3], &[128, 128, 128]);
+        let buf = displayed(2, 2, |r| {
+            r.set_gamma_correction(true);
+            draw(r);
+        });
+        assert_eq!(&buf[.. 3], &[186, 186, 186]);
+    }
+
+    #[test]
+    fn hdr_frame_goes_past_white() {
+        let buf = displayed(4, 4, |r| {
+            r.set_hdr(true);
+            r.set_color(pixel::WHITE);
+            r.fill_triangle(
+                trigon![pt![-1., -1., 5.], pt![-1., 9., 5.], pt![9., -1., 5.]]
+            );
+            r.set_depth_test(DepthTest::Always);
+            r.set_blend_mode(BlendMode::Additive);
+            r.fill_triangle(
+                trigon![pt![-1., -1., 5.], pt![-1., 2., 5.], pt![2., -1., 5.]]
+            );
+
+            assert_eq!(r.snapshot().read_pixel(3, 0), Some(pixel::WHITE));
+            let hdr = r.hdr_buffer_mut().unwrap();
+            assert_eq!(hdr.pixels[0], (2., 2., 2.));
+            assert_eq!(hdr.pixels[3], (1., 1., 1.));
+        });
+
+        // Twice white is the white point; white itself is compressed.
+        assert_eq!(&buf[.. 3], &[255, 255, 255]);
+        assert_eq!(&buf[9 .. 12], &[159, 159, 159]);
+    }
+
+    #[test]
+    fn hdr_without_overbright_pixels_displays_unchanged() {
+        let buf = displayed(2, 2, |r| {
+            r.set_hdr(true);
+            r.set_clear_color((255, 128, 0));
+            r.clear();
+        });
+        assert_eq!(&buf[.. 3], &[255, 128, 0]);
+    }
+
+    #[test]
+    fn scale_uniform_prepends_to_the_transform() {
+        let mut r = renderer(4, 4);
+        r.translate(pt![1., 0., 0.]);
+        r.scale_uniform(3.);
+        // Applied after the translation: (1 + 1) * 3.
+        let p = pt![1., 1., -2.] * r.transform;
+        assert_eq!((p.x, p.y, p.z), (6., 3., -6.));
+    }
+
+    #[test]
+    fn color_stack_restores_colors() {
+        let mut r = renderer(4, 1);
+        r.set_point_size(1.);
+        r.set_color(pixel::GREEN);
+        r.push_color();
+        r.set_color(pixel::RED);
+        r.draw_point(pt![0., 0., 0.]);
+        r.push_color();
+        r.set_color(pixel::BLUE);
+        r.draw_point(pt![1., 0., 0.]);
+        r.pop_color();
+        assert_eq!(r.color, pixel::RED);
+        r.pop_color();
+        r.draw_point(pt![2., 0., 0.]);
+        // Popping an empty stack keeps the color.
+        r.pop_color();
+        r.draw_point(pt![3., 0., 0.]);
+
+        let frame = r.snapshot();
+        assert_eq!(
+            frame.pixels,
+            vec![pixel::RED, pixel::BLUE, pixel::GREEN, pixel::GREEN]
+        );
+    }
+
+    #[test]
+    fn grayscale_mode_grays_written_pixels() {
+        let mut r = renderer(2, 1);
+        r.set_point_size(1.);
+        r.set_color((255, 128, 0));
+        r.set_grayscale_mode(true);
+        r.draw_point(pt![0., 0., 0.]);
+        r.set_grayscale_mode(false);
+        r.draw_point(pt![1., 0., 0.]);
+        assert_eq!(r.snapshot().pixels, vec![(151, 151, 151), (255, 128, 0)]);
+    }
+
+    fn lit(r: &Renderer<TextScreen>, x: PixCoord, y: PixCoord) -> bool {
+        r.snapshot().read_pixel(x, y).unwrap_or(pixel::BLACK) != pixel::BLACK
+    }
+
+    #[test]
+    fn bezier_cubic_runs_from_p0_to_p3() {
+        let (p0, p1) = (pt![1., 14., 0.], pt![1., 0., 0.]);
+        let (p2, p3) = (pt![14., 0., 0.], pt![14., 14., 0.]);
+        let mut r = renderer(16, 16);
+        r.draw_bezier_cubic(p0, p1, p2, p3, 16);
+        assert!(lit(&r, 1, 14));
+        assert!(lit(&r, 14, 14));
+        // The curve passes (7.5, 3.5) at t = 0.5, well off the chord.
+        assert!(lit(&r, 7, 3) || lit(&r, 8, 3));
+        let curved = lit_pixels(&r);
+
+        // A single step is the chord from p0 to p3.
+        let mut r = renderer(16, 16);
+        r.draw_bezier_cubic(p0, p1, p2, p3, 1);
+        assert!(lit(&r, 1, 14) && lit(&r, 14, 14));
+        assert!(lit_pixels(&r) < curved);
+    }
+
+    #[test]
+    fn bezier_quadratic_runs_from_p0_to_p2() {
+        let (p0, p1) = (pt![1., 14., 0.], pt![8., -6., 0.]);
+        let p2 = pt![14., 14., 0.];
+        let coverage = |steps: usize| {
+            let mut r = renderer(16, 16);
+            r.draw_bezier_quadratic(p0, p1, p2, steps);
+            assert!(lit(&r, 1, 14) && lit(&r, 14, 14));
+            lit_pixels(&r)
+        };
+        assert!(coverage(2) < coverage(16));
+        assert!(coverage(1) < coverage(2));
+    }
+
+    #[test]
+    fn billboard_center_shows_the_texture_center() {
+        let mut tex = Texture::new(3, 3);
+        tex.set_all_pixels(pixel::BLUE);
+        tex.pixels[4] = pixel::RED;
+
+        let mut r = renderer(16, 16);
+        r.draw_billboard(pt![8., 8., 5.], 12., 12., &tex);
+        let frame = r.snapshot();
+        assert_eq!(frame.read_pixel(8, 8), Some(pixel::RED));
+        assert_eq!(frame.read_pixel(3, 3), Some(pixel::BLUE));
+        assert_eq!(frame.read_pixel(12, 12), Some(pixel::BLUE));
+        assert_eq!(frame.read_pixel(0, 0), Some(pixel::BLACK));
+        assert_eq!(frame.read_depth(8, 8), Some(5.));
+    }
+
+    #[test]
+    fn draw_text_near_the_coordinate_limits() {
+        let mut r = renderer(16, 16);
+        r.draw_text("hello", i16::MAX - 4, i16::MAX - 4);
+        r.draw_text("hello", i16::MIN, i16::MIN);
+        assert_eq!(lit_pixels(&r), 0);
+
+        // Only the last glyph of a line starting far left is on screen.
+        let line = "W".repeat(4096) + "A";
+        r.draw_text(&line, -8 * 4096, 0);
+        let shifted = r.snapshot();
+        let mut r = renderer(16, 16);
+        r.draw_text("A", 0, 0);
+        assert!(lit_pixels(&r) > 0);
+        assert!(shifted.compare(&r.snapshot(), 0));
+    }
+
+    #[test]
+    fn draw_text_continues_after_a_long_line() {
+        let mut r = renderer(16, 16);
+        let text = "W".repeat(10000) + "\nA";
+        r.draw_text(&text, 0, -8);
+        let frame = r.snapshot();
+
+        let mut r = renderer(16, 16);
+        r.draw_text("A", 0, 0);
+        assert!(frame.compare(&r.snapshot(), 0));
+    }
+
+    fn numbered_sprite(w: Dimension, h: Dimension) -> Texture {
+        let mut t = Texture::new(w, h);
+        for (i, p) in t.pixels.iter_mut().enumerate() {
+            *p = (i as u8 + 1, 0, 0);
+        }
+        t
+    }
+
+    #[test]
+    fn draw_sprite_clips_to_the_texture() {
+        let tex = numbered_sprite(4, 4);
+        let sprite = TextureView::whole(&tex);
+        let mut r = renderer(8, 8);
+        r.draw_sprite(&sprite, -2, -1);
+        let frame = r.snapshot();
+        // Texel (2, 1) lands on the corner.
+        assert_eq!(frame.read_pixel(0, 0), Some((7, 0, 0)));
+        assert_eq!(frame.read_pixel(1, 2), Some((16, 0, 0)));
+        assert_eq!(frame.read_pixel(2, 0), Some(pixel::BLACK));
+        assert_eq!(lit_pixels(&r), 6);
+
+        let mut r = renderer(8, 8);
+        r.draw_sprite(&sprite, 6, 7);
+        assert_eq!(r.snapshot().read_pixel(7, 7), Some((2, 0, 0)));
+        assert_eq!(lit_pixels(&r), 2);
+    }
+
+    #[test]
+    fn draw_sprite_near_the_coordinate_limits() {
+        let tex = numbered_sprite(4, 4);
+        let sprite = TextureView::whole(&tex);
+        let mut r = renderer(8, 8);
+        r.draw_sprite(&sprite, i16::MAX - 1, i16::MAX - 1);
+        r.draw_sprite(&sprite, i16::MIN, i16::MIN);
+        assert_eq!(lit_pixels(&r), 0);
+
+        // Wider than PixCoord can count.
+        let mut tex = Texture::new(40000, 1);
+        tex.set_all_pixels(pixel::WHITE);
+        let mut r = renderer(8, 1);
+        r.draw_sprite(&TextureView::whole(&tex), 1, 0);
+        assert_eq!(lit_pixels(&r), 7);
+        r.draw_sprite(&TextureView::whole(&tex), i16::MIN, 0);
+        assert_eq!(lit_pixels(&r), 8);
+    }
+
+    #[test]
+    fn draw_axes_colors() {
+        let buf = displayed(16, 16, |r| {
+            r.set_color(pixel::WHITE);
+            // Sheared so the Z axis runs diagonally down the screen.
+            r.shear(0., 0., 0., 0., 1., 1.);
+            r.translate(pt![2., 2., 0.]);
+            r.draw_axes(10.);
+            assert_eq!(r.color, pixel::WHITE);
+        });
+        let at = |x: usize, y: usize| {
+            let i = 3 * (y * 16 + x);
+            (buf[i], buf[i + 1], buf[i + 2])
+        };
+        assert_eq!(at(7, 2), pixel::RED);
+        assert_eq!(at(12, 2), pixel::RED);
+        assert_eq!(at(2, 7), pixel::GREEN);
+        assert_eq!(at(2, 12), pixel::GREEN);
+        assert_eq!(at(7, 7), pixel::BLUE);
+        assert_eq!(at(12, 12), pixel::BLUE);
+        assert_eq!(at(7, 12), pixel::BLACK);
+        assert_eq!(at(13, 2), pixel::BLACK);
+    }
+
+    #[test]
+    fn draw_grid_lit_pixels() {
+        let mut r = renderer(16, 16);
+        r.set_color(pixel::WHITE);
+        // Tipped over so the XZ plane faces the screen.
+        r.rotate_x(f64::consts::FRAC_PI_2);
+        r.translate(pt![8., 8., 0.]);
+        r.draw_grid(8., 4);
+        // Five rows and five columns of nine pixels, crossing 25 times.
+        assert_eq!(lit_pixels(&r), 5 * 9 + 5 * 9 - 25);
+        assert!(lit(&r, 4, 4) && lit(&r, 12, 12) && lit(&r, 6, 9));
+        assert!(!lit(&r, 5, 5) && !lit(&r, 3, 4) && !lit(&r, 13, 12));
+    }
+
+    #[test]
+    fn draw_aabb_sets_edge_pixels() {
+        let mut r = renderer(16, 16);
+        r.set_color(pixel::WHITE);
+        r.scale_uniform(8.);
+        r.translate(pt![4., 4., 0.]);
+        r.draw_aabb(&Aabb::new(pt![0., 0., 0.], pt![1., 1., 1.]));
+        // Seen head on, the front and back faces share one square outline.
+        assert!(lit(&r, 4, 4) && lit(&r, 8, 4) && lit(&r, 4, 8));
+        assert!(lit(&r, 12, 12));
+        assert!(!lit(&r, 8, 8) && !lit(&r, 3, 4) && !lit(&r, 13, 12));
+        assert_eq!(lit_pixels(&r), 4 * 8);
+    }
+
+    #[test]
+    fn snapshot_outlives_a_clear() {
+        let mut r = renderer(16, 16);
+        r.set_color(pixel::RED);
+        r.fill_triangle(
+            trigon![pt![0., 0., 1.], pt![0., 16., 1.], pt![16., 0., 1.]]
+        );
+        let frame = r.snapshot();
+        r.clear();
+        assert_eq!(lit_pixels(&r), 0);
+        assert_eq!(frame.read_pixel(2, 2), Some(pixel::RED));
+        assert_eq!(frame.read_pixel(0, 12), Some(pixel::RED));
+        assert_eq!(frame.read_pixel(14, 14), Some(pixel::BLACK));
+    }
+
+    #[test]
+    fn gradient_middle_is_the_average() {
+        let (a, b) = ((0, 40, 255), (200, 100, 55));
+        let mut r = renderer(4, 5);
+        r.fill_triangle(
+            trigon![pt![0., 0., 1.], pt![0., 5., 1.], pt![4., 0., 1.]]
+        );
+        r.clear_with_gradient(a, b);
+        let frame = r.snapshot();
+        for x in 0 .. 4 {
+            assert_eq!(frame.read_pixel(x, 0), Some(a));
+            assert_eq!(frame.read_pixel(x, 2), Some((100, 70, 155)));
+            assert_eq!(frame.read_pixel(x, 4), Some(b));
+        }
+        // The depths are cleared along with the colors.
+        let cleared = renderer(4, 5).snapshot().read_depth(0, 0);
+        assert_eq!(frame.read_depth(0, 0), cleared);
+
+        let mut r = renderer(5, 2);
+        r.clear_with_gradient_h(a, b);
+        let frame = r.snapshot();
+        for y in 0 .. 2 {
+            assert_eq!(frame.read_pixel(0, y), Some(a));
+            assert_eq!(frame.read_pixel(2, y), Some((100, 70, 155)));
+            assert_eq!(frame.read_pixel(4, y), Some(b));
+        }
+    }
+
+    #[test]
+    fn lighting_follows_a_non_uniform_model_scale() {
+        // Tilted about the y axis, with a normal along (1, 0, -4).
+        let t = trigon![pt![0., 0., 1.], pt![0., 16., 1.], pt![16., 0., 5.]];
+        let scale = Transform::scale(1., 1., 4.);
+        let lit_at = |model: Transform, t: Triangle| {
+            let mut r = renderer(16, 16);
+            r.set_lighting_mode(LightingMode::FlatShading);
+            r.set_light_pos(pt![8., 8., -1000.]);
+            r.set_model_transform(model);
+            r.fill_triangle(t);
+            r.snapshot().read_pixel(2, 2).unwrap()
+        };
+
+        // Facing the light almost head on, and turned a lot further away
+        // once stretched along z, where the normal is along (1, 0, -1).
+        let before = lit_at(Transform::identity(), t);
+        let after = lit_at(scale, t);
+        assert!(before.0 > 240, "{:?}", before);
+        assert!(after.0 > 170 && after.0 < 190, "{:?}", after);
+
+        // The same as scaling the vertices by hand.
+        assert_eq!(after, lit_at(Transform::identity(), t * scale));
+
+        // Normals handed in go through the normal matrix too.
+        let mut r = renderer(16, 16);
+        r.set_lighting_mode(LightingMode::FlatShading);
+        r.set_light_pos(pt![8., 8., -1000.]);
+        r.set_model_transform(scale);
+        r.fill_triangle_with_normal(t, t.normal());
+        assert_eq!(r.snapshot().read_pixel(2, 2), Some(after));
+    }
+
+    #[test]
+    fn state_guard_restores_the_settings() {
+        let mut r = renderer(16, 16);
+        r.set_point_size(1.);
+        r.set_color(pixel::RED);
+        {
+            let mut _guard = r.push_state();
+            _guard.translate(pt![5., 5., 0.]);
+            _guard.set_model_transform(Transform::scale_uniform(2.));
+            _guard.set_viewport(8, 8, 8, 8);
+            _guard.set_color(pixel::BLUE);
+            _guard.set_shininess(4.);
+            _guard.set_line_width(3.);
+            _guard.set_line_antialiasing(true);
+            _guard.set_grayscale_mode(true);
+            _guard.set_cull_mode(CullMode::None);
+            _guard.set_render_mode(RenderMode::Wireframe);
+            _guard.set_blend_mode(BlendMode::Additive);
+            _guard.set_clear_color(pixel::GREEN);
+            _guard.add_clip_plane(pt![1., 0., 0.], 0.);
+        }
+
+        assert_eq!(r.color, pixel::RED);
+        assert_eq!(r.shininess, 32.);
+        assert_eq!(r.line_width, 1.);
+        assert!(!r.line_antialiasing && !r.grayscale);
+        assert_eq!(r.cull_mode, CullMode::Back);
+        assert_eq!(r.render_mode, RenderMode::Solid);
+        assert_eq!(r.texture.blend_mode(), BlendMode::Replace);
+        assert_eq!(r.clear_color, pixel::BLACK);
+        assert!(r.viewport.is_none() && r.clip_planes.is_empty());
+
+        // Drawing lands where it would have without the guard.
+        r.draw_point(pt![2., 3., 0.]);
+        assert!(lit(&r, 2, 3));
+        assert_eq!(lit_pixels(&r), 1);
+        assert_eq!(r.snapshot().read_pixel(2, 3), Some(pixel::RED));
+    }
+
+    #[test]
+    fn filled_triangles_cover_the_skybox() {
+        let mut textures = Vec::new();
+        for &color in &[pixel::RED, pixel::BLUE] {
+            let mut tex = Texture::new(2, 2);
+            tex.set_all_pixels(color);
+            textures.push(tex);
+        }
+        let (red, blue) = (&textures[0], &textures[1]);
+        // Looking down -z, straight at the blue -Z face.
+        let faces = [red, red, red, red, red, blue];
+        let triangle =
+            trigon![pt![-1., -1., -3.], pt![1., -1., -3.], pt![0., 1., -3.]];
+        let setup = |r: &mut Renderer<TextScreen>| {
+            r.set_transform(
+                Transform::translate(pt![8., 8., 0.])
+                    * Transform::scale(8., 8., 1.)
+                    * Transform::perspective()
+            );
+            r.set_cull_mode(CullMode::None);
+            r.set_color(pixel::GREEN);
+        };
+
+        // Drawn over the sky, and kept when the sky comes after.
+        let mut r = renderer(16, 16);
+        setup(&mut r);
+        r.draw_skybox(faces);
+        assert_eq!(r.snapshot().read_pixel(8, 8), Some(pixel::BLUE));
+        r.fill_triangle(triangle);
+        let frame = r.snapshot();
+        assert_eq!(frame.read_pixel(8, 8), Some(pixel::GREEN));
+        assert_eq!(frame.read_pixel(1, 1), Some(pixel::BLUE));
+
+        let mut r = renderer(16, 16);
+        setup(&mut r);
+        r.fill_triangle(triangle);
+        r.draw_skybox(faces);
+        assert!(r.snapshot().compare(&frame, 0));
+    }
+
+    #[test]
+    fn terrain_from_a_3x3_height_map() {
+        let heights = [
+            0., 1., 2.,
+            1., 2., 3.,
+            2., 3., 4.,
+        ];
+        let mut r = renderer(16, 16);
+        // Seen from above, with z running down the screen.
+        r.rotate_x(f64::consts::FRAC_PI_2);
+        r.translate(pt![4., 4., 0.]);
+        r.set_cull_mode(CullMode::None);
+        r.draw_terrain(&heights, 3, 3, 4.);
+
+        // Four quads colored by their mean height, from 1 to 3 of 0 to 4.
+        let frame = r.snapshot();
+        let quarter = pixel::lerp(pixel::BLUE, pixel::WHITE, 0.25);
+        let half = pixel::lerp(pixel::BLUE, pixel::WHITE, 0.5);
+        let three_quarters = pixel::lerp(pixel::BLUE, pixel::WHITE, 0.75);
+        assert_eq!(frame.read_pixel(6, 6), Some(quarter));
+        assert_eq!(frame.read_pixel(10, 6), Some(half));
+        assert_eq!(frame.read_pixel(6, 10), Some(half));
+        assert_eq!(frame.read_pixel(10, 10), Some(three_quarters));
+        // Both triangles of each quad are there.
+        for &(x, y) in &[(5, 5), (7, 7), (9, 9), (11, 11), (5, 11), (11, 5)] {
+            assert!(lit(&r, x, y), "({}, {})", x, y);
+        }
+        assert!(!lit(&r, 2, 2) && !lit(&r, 14, 8) && !lit(&r, 8, 14));
+
+        // Too few entries, rows or columns draw nothing.
+        let mut r = renderer(16, 16);
+        r.draw_terrain(&heights[.. 8], 3, 3, 4.);
+        r.draw_terrain(&heights, 1, 9, 4.);
+        assert_eq!(lit_pixels(&r), 0);
+    }
+
+    #[test]
+    fn clear_depth_keeps_the_frame() {
+        let mut r = renderer(16, 16);
+        r.set_color(pixel::RED);
+        r.fill_triangle(
+            trigon![pt![0., 0., 1.], pt![0., 16., 1.], pt![16., 0., 1.]]
+        );
+        let frame = r.snapshot();
+        r.clear_depth();
+        assert!(r.snapshot().compare(&frame, 0));
+        assert_eq!(r.snapshot().read_depth(2, 2), Some(f64::INFINITY));
+    }
+
+    // Draws `t` once through `fill_triangle` and once Gouraud shaded after
+    // `setup`, with lighting off so both fill in the current color.
+    fn flat_and_gouraud<F>(t: Triangle, setup: F) -> (Texture, Texture)
+        where F: Fn(&mut Renderer<TextScreen>)
+    {
+        let draw = |gouraud: bool| {
+            let mut r = renderer(16, 16);
+            r.set_lighting_mode(LightingMode::NoShading);
+            r.set_color(pixel::RED);
+            setup(&mut r);
+            if gouraud {
+                let n = pt![0., 0., -1.];
+                r.draw_indexed_triangles_with_normals(
+                    &t.to_arr(),
+                    &[n, n, n],
+                    &[(0, 1, 2)]
+                );
+            } else {
+                r.fill_triangle(t);
+            }
+            r.snapshot()
+        };
+        (draw(false), draw(true))
+    }
+
+    #[test]
+    fn gouraud_triangles_share_the_fill_front_end() {
+        let t = trigon![pt![1., 1., 5.], pt![14., 2., 5.], pt![3., 14., 5.]];
+        let flipped = trigon![t.p1, t.p3, t.p2];
+        let degenerate = trigon![t.p1, t.p2, t.p1];
+        let setups: [fn(&mut Renderer<TextScreen>); 6] = [
+            |_| {},
+            |r| r.set_cull_mode(CullMode::Front),
+            |r| r.set_cull_mode(CullMode::None),
+            |r| r.set_render_mode(RenderMode::Wireframe),
+            |r| r.set_render_mode(RenderMode::SolidWireframe),
+            |r| r.add_clip_plane(pt![1., 0., 0.], -7.),
+        ];
+        let any_lit = |t: &Texture| t.pixels.iter().any(|&p| p != pixel::BLACK);
+        let mut drawn = 0;
+        for setup in &setups {
+            for &tri in &[t, flipped, degenerate] {
+                let (flat, gouraud) = flat_and_gouraud(tri, *setup);
+                assert_eq!(any_lit(&flat), any_lit(&gouraud));
+                if any_lit(&gouraud) { drawn += 1 }
+            }
+        }
+        // Each setup draws exactly one winding, except CullMode::None which
+        // draws both.
+        assert_eq!(drawn, setups.len() + 1);
+
+        // Wireframes are drawn the same way for both.
+        let (flat, gouraud) = flat_and_gouraud(t, setups[3]);
+        assert!(flat.compare(&gouraud, 0));
+
+        // Nothing is left left of the clip plane.
+        let (_, gouraud) = flat_and_gouraud(t, setups[5]);
+        for (x, _, p, _) in gouraud.pixels_with_coords() {
+            assert!(x >= 7 || p == pixel::BLACK);
+        }
+    }
+
+    #[test]
+    fn phong_triangles_share_the_fill_front_end() {
+        let t = trigon![pt![1., 1., 5.], pt![3., 14., 5.], pt![14., 2., 5.]];
+        let flipped = trigon![t.p1, t.p3, t.p2];
+        let setups: [fn(&mut Renderer<TextScreen>); 5] = [
+            |_| {},
+            |r| r.set_cull_mode(CullMode::Front),
+            |r| r.set_cull_mode(CullMode::None),
+            |r| r.set_render_mode(RenderMode::Wireframe),
+            |r| r.add_clip_plane(pt![1., 0., 0.], -7.),
+        ];
+        // Full ambient light so faces turned from the light still show.
+        let phong = |t: Triangle, setup: fn(&mut Renderer<TextScreen>)| {
+            let mut r = renderer(16, 16);
+            r.set_ambient(1.);
+            r.set_color(pixel::RED);
+            setup(&mut r);
+            let n = pt![0., 0., -1.];
+            let light = pt![8., 8., -10.];
+            r.fill_phong_triangle(t, [n, n, n], light, light);
+            r.snapshot()
+        };
+        let any_lit = |t: &Texture| t.pixels.iter().any(|&p| p != pixel::BLACK);
+        for &setup in &setups {
+            for &tri in &[t, flipped] {
+                let (flat, _) = flat_and_gouraud(tri, setup);
+                assert_eq!(any_lit(&flat), any_lit(&phong(tri, setup)));
+            }
+        }
+        assert!(!any_lit(&phong(flipped, setups[0])));
+        assert!(any_lit(&phong(flipped, setups[1])));
+        assert!(!any_lit(&phong(t, setups[1])));
+
+        // Wireframes are drawn the same way for both.
+        let (flat, _) = flat_and_gouraud(t, setups[3]);
+        assert!(flat.compare(&phong(t, setups[3]), 0));
+
+        // Nothing is left left of the clip plane.
+        let clipped = phong(t, setups[4]);
+        assert!(any_lit(&clipped));
+        for (x, _, p, _) in clipped.pixels_with_coords() {
+            assert!(x >= 7 || p == pixel::BLACK);
+        }
+    }
+
+    #[test]
+    fn clipped_gouraud_pieces_keep_their_colors() {
+        let t = trigon![pt![0., 0., 0.], pt![4., 0., 0.], pt![0., 4., 0.]];
+        let color = FaceColor::Gouraud([pixel::RED, pixel::GREEN, pixel::BLUE]);
+        let piece = trigon![pt![2., 0., 0.], pt![0., 2., 0.], t.p1];
+        match color.of_piece(t, piece) {
+            FaceColor::Gouraud(colors) => assert_eq!(
+                colors,
+                [(128, 128, 0), (128, 0, 128), pixel::RED]
+            ),
+            _ => panic!("piece lost its vertex colors"),
+        }
+    }
+
+    #[test]
+    fn draw_text_3d_depth_test() {
+        let mut r = renderer(32, 32);
+        r.fill_triangle(
+            trigon![pt![0., 0., 5.], pt![0., 32., 5.], pt![32., 0., 5.]]
+        );
+        r.set_color(pixel::RED);
+        let count_red = |r: &Renderer<TextScreen>| {
+            r.snapshot().pixels.iter().filter(|&&p| p == pixel::RED).count()
+        };
+        r.draw_text_3d("A", pt![4., 4., 9.], true);
+        assert_eq!(count_red(&r), 0);
+        r.draw_text_3d("A", pt![4., 4., 1.], true);
+        assert!(count_red(&r) > 0);
+    }
+
+    #[test]
+    fn draw_text_3d_off_screen() {
+        let mut r = renderer(32, 32);
+        r.draw_text_3d("A", pt![1e9, 4., 0.], false);
+        r.draw_text_3d("A", pt![-40., 4., 0.], false);
+        r.draw_text_3d("A", pt![4., f64::NAN, 0.], false);
+        assert_eq!(lit_pixels(&r), 0);
+
+        // Behind the camera.
+        r.perspective();
+        r.draw_text_3d("A", pt![0., 0., 1.], false);
+        assert_eq!(lit_pixels(&r), 0);
+    }
+
+    #[test]
+    fn draw_point_far_left_of_screen() {
+        let mut r = renderer(8, 8);
+        r.draw_point(pt![-1000., 4., 0.]);
+        assert_eq!(lit_pixels(&r), 0);
+    }
+
+    #[test]
+    fn particles_far_off_screen_or_huge() {
+        let mut r = renderer(8, 8);
+        let far = [
+            pt![-1e6, 4., 0.],
+            pt![1e6, 4., 0.],
+            pt![4., -1e6, 0.],
+            pt![4., 1e6, 0.],
+            pt![1e300, -1e300, 0.],
+            pt![f64::NAN, 4., 0.],
+        ];
+        r.draw_particles(&far, 3.);
+        assert_eq!(lit_pixels(&r), 0);
+
+        // Sizes past what Dimension holds cover the screen and no more.
+        r.draw_particles(&[pt![4., 4., 0.]], 1e12);
+        assert_eq!(lit_pixels(&r), 64);
+
+        // So does a point with a tiny w, just in front of the camera.
+        let mut r = renderer(8, 8);
+        r.perspective();
+        r.draw_point(pt![0., 0., -1e-12]);
+        assert_eq!(lit_pixels(&r), 64);
+
+        // Clipped at the texture edge.
+        let mut r = renderer(8, 8);
+        r.draw_particles(&[pt![0., 7., 0.]], 3.);
+        assert_eq!(lit_pixels(&r), 4);
+    }
+
+    #[test]
+    fn point_sizes_follow_w() {
+        let drawn = |p: Point, size: f64| {
+            let mut r = renderer(8, 8);
+            r.perspective();
+            r.translate(pt![4., 4., 0.]);
+            r.draw_point_with_size(p, size);
+            lit_pixels(&r)
+        };
+        assert_eq!(drawn(pt![0., 0., -1.], 4.), 16);
+        assert_eq!(drawn(pt![0., 0., -2.], 4.), 4);
+        // Never less than a pixel, and nothing behind the camera.
+        assert_eq!(drawn(pt![0., 0., -100.], 4.), 1);
+        assert_eq!(drawn(pt![0., 0., 1.], 4.), 0);
+
+        // Far off screen, and close enough to cover all of it.
+        assert_eq!(drawn(pt![-1e6, 0., -1.], 4.), 0);
+        assert_eq!(drawn(pt![0., 1e6, -1.], 4.), 0);
+        assert_eq!(drawn(pt![0., 0., -1e-12], 4.), 64);
+        assert_eq!(drawn(pt![1e-6, 0., -1e-6], 4.), 64);
+    }
+
+    #[test]
+    fn emissive_lights_faces_turned_from_the_light() {
+        let t = trigon![pt![0., 0., 1.], pt![0., 16., 1.], pt![16., 0., 1.]];
+        let draw = |emissive: Pixel| {
+            let mut r = renderer(16, 16);
+            r.set_lighting_mode(LightingMode::FlatShading);
+            // Behind the triangle, which faces -z.
+            r.set_light_pos(pt![8., 8., 1000.]);
+            r.set_color((200, 100, 50));
+            r.set_emissive(emissive);
+            r.fill_triangle(t);
+            r.snapshot().read_pixel(2, 2).unwrap()
+        };
+        assert_eq!(draw(pixel::BLACK), pixel::BLACK);
+        assert_eq!(draw((30, 0, 90)), (30, 0, 90));
+
+        // Added to lit faces too, clamped at white.
+        let mut r = renderer(16, 16);
+        r.set_lighting_mode(LightingMode::FlatShading);
+        r.set_light_pos(pt![8., 8., -1000.]);
+        r.set_color((200, 100, 50));
+        r.set_emissive((100, 100, 0));
+        r.fill_triangle(t);
+        assert_eq!(r.snapshot().read_pixel(2, 2), Some((255, 199, 49)));
+    }
+
+    #[test]
+    fn fog_equations_at_known_depths() {
+        let linear = Fog::linear(pixel::WHITE, 10., 30.);
+        assert_eq!(linear.visibility(0.), 1.);
+        assert_eq!(linear.visibility(10.), 1.);
+        assert_eq!(linear.visibility(15.), 0.75);
+        assert_eq!(linear.visibility(20.), 0.5);
+        assert_eq!(linear.visibility(30.), 0.);
+        assert_eq!(linear.visibility(100.), 0.);
+        // With no distance to fade over, the fog starts all at once.
+        let sudden = Fog::linear(pixel::WHITE, 10., 10.);
+        assert_eq!(sudden.visibility(9.9), 1.);
+        assert_eq!(sudden.visibility(10.), 0.);
+
+        let exp = Fog::exp(pixel::WHITE, 0.1);
+        assert_eq!(exp.visibility(0.), 1.);
+        assert_near(exp.visibility(10.), (-1f64).exp());
+        assert_near(exp.visibility(20.), (-2f64).exp());
+
+        let exp2 = Fog::exp2(pixel::WHITE, 0.1);
+        assert_eq!(exp2.visibility(0.), 1.);
+        assert_near(exp2.visibility(10.), (-1f64).exp());
+        assert_near(exp2.visibility(20.), (-4f64).exp());
+        assert!(exp2.visibility(5.) > exp.visibility(5.));
+
+        // Halfway through linear fog, a mid-gray blend.
+        assert_eq!(linear.apply(pixel::BLACK, 20.), (128, 128, 128));
+    }
+
+    #[test]
+    fn fog_blends_filled_triangles() {
+        let fogged = |fog: Fog| {
+            let mut r = renderer(16, 16);
+            r.set_color(pixel::RED);
+            r.set_fog(Some(fog));
+            r.fill_triangle(
+                trigon![pt![0., 0., 10.], pt![0., 16., 10.], pt![16., 0., 10.]]
+            );
+            r.snapshot().read_pixel(2, 2).unwrap()
+        };
+        // The pixel is a little over 10 away from the origin.
+        assert_eq!(fogged(Fog::linear(pixel::BLUE, 20., 30.)), pixel::RED);
+        assert_eq!(fogged(Fog::linear(pixel::BLUE, 0., 5.)), pixel::BLUE);
+        assert_eq!(fogged(Fog::exp(pixel::BLUE, 0.)), pixel::RED);
+        let (r, g, b) = fogged(Fog::exp2(pixel::BLUE, 0.1));
+        // About exp(-1.1) of the red is left.
+        assert!(r > 75 && r < 95 && g == 0 && b > 160, "{:?}", (r, g, b));
+    }
+
+    fn assert_near(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn wide_lines_cover_their_width() {
+        let column = |r: &Renderer<TextScreen>, x: PixCoord| {
+            (0 .. 16).filter(|&y| lit(r, x, y)).collect::<Vec<_>>()
+        };
+        let row = |r: &Renderer<TextScreen>, y: PixCoord| {
+            (0 .. 16).filter(|&x| lit(r, x, y)).collect::<Vec<_>>()
+        };
+
+        let mut r = renderer(16, 16);
+        r.draw_line(pt![2., 8., 0.], pt![13., 8., 0.]);
+        assert_eq!(column(&r, 7), vec![8]);
+
+        let mut r = renderer(16, 16);
+        r.set_line_width(3.);
+        r.draw_line(pt![2., 8., 0.], pt![13., 8., 0.]);
+        assert_eq!(column(&r, 7), vec![7, 8, 9]);
+
+        let mut r = renderer(16, 16);
+        r.set_line_width(3.);
+        r.draw_line(pt![8., 2., 0.], pt![8., 13., 0.]);
+        assert_eq!(row(&r, 7), vec![7, 8, 9]);
+
+        // Across a diagonal, three pixels wide means at least four pixels
+        // of any row it crosses.
+        let mut r = renderer(16, 16);
+        r.set_line_width(3.);
+        r.draw_line(pt![2., 2., 0.], pt![13., 13., 0.]);
+        let crossing = row(&r, 7);
+        assert!(crossing.len() >= 4, "{:?}", crossing);
+        assert!(crossing.contains(&7));
+        for pair in crossing.windows(2) { assert_eq!(pair[1], pair[0] + 1) }
+    }
+
+    #[test]
+    fn distant_lod_meshes_draw_fewer_triangles() {
+        let coarse = vec![
+            trigon![pt![2., 2., 1.], pt![2., 14., 1.], pt![14., 2., 1.]],
+            trigon![pt![14., 2., 1.], pt![2., 14., 1.], pt![14., 14., 1.]],
+        ];
+        let fine = mesh::subdivide_mesh(&coarse, 1);
+        let lod = LODMesh::new(vec![
+            (10., Mesh::new(fine)),
+            (f64::INFINITY, Mesh::new(coarse.clone())),
+        ]);
+        // Wireframes show how many triangles were drawn.
+        let frame = |draw: &Fn(&mut Renderer<TextScreen>)| {
+            let mut r = renderer(16, 16);
+            r.set_render_mode(RenderMode::Wireframe);
+            draw(&mut r);
+            r
+        };
+
+        let near = frame(&|r| r.draw_lod_mesh(&lod, pt![8., 8., -5.]));
+        let far = frame(&|r| r.draw_lod_mesh(&lod, pt![8., 8., -500.]));
+        assert!(lit_pixels(&near) > lit_pixels(&far));
+        let coarse = frame(&|r| r.draw_mesh(&Mesh::new(coarse.clone())));
+        assert!(far.snapshot().compare(&coarse.snapshot(), 0));
     }
 }
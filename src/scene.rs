@@ -0,0 +1,481 @@
+use std::error;
+use std::fmt::Write;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use mesh::Mesh;
+use types::*;
+
+
+#[derive(Clone, Copy)]
+pub struct LightSource {
+    pub position: Point,
+}
+
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub transform: Transform,
+}
+
+// Everything needed to redraw a scene. Scenes are stored as a small subset
+// of TOML: numbers and (possibly nested) arrays of numbers, a `[camera]`
+// table, and `[[lights]]` and `[[meshes]]` arrays of tables.
+#[derive(Clone)]
+pub struct Scene {
+    pub meshes: Vec<(Mesh, Transform)>,
+    pub lights: Vec<LightSource>,
+    pub camera: Camera,
+    pub ambient: f64,
+}
+
+impl Default for Scene {
+    fn default() -> Scene {
+        Scene {
+            meshes: Vec::new(),
+            lights: Vec::new(),
+            camera: Camera { transform: Transform::identity() },
+            ambient: 0.,
+        }
+    }
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<error::Error>> {
+        fs::write(path, self.to_toml())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Scene, Box<error::Error>> {
+        let mut text = String::new();
+        fs::File::open(path)?.read_to_string(&mut text)?;
+        Scene::from_toml(&text)
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail.
+        writeln!(out, "ambient = {}", toml_number(self.ambient)).unwrap();
+
+        writeln!(out, "\n[camera]").unwrap();
+        let camera = transform_to_toml(self.camera.transform);
+        writeln!(out, "transform = {}", camera).unwrap();
+
+        for light in &self.lights {
+            writeln!(out, "\n[[lights]]").unwrap();
+            writeln!(out, "position = {}", point_to_toml(light.position))
+                .unwrap();
+        }
+
+        for &(ref mesh, transform) in &self.meshes {
+            writeln!(out, "\n[[meshes]]").unwrap();
+            writeln!(out, "transform = {}", transform_to_toml(transform))
+                .unwrap();
+            writeln!(out, "triangles = [").unwrap();
+            for t in &mesh.triangles {
+                let coords: Vec<String> = t.to_arr().iter()
+                    .flat_map(|p| vec![p.x, p.y, p.z])
+                    .map(toml_number)
+                    .collect();
+                writeln!(out, "    [{}],", coords.join(", ")).unwrap();
+            }
+            writeln!(out, "]").unwrap();
+            if let Some(ref normals) = mesh.normals {
+                writeln!(out, "normals = [").unwrap();
+                for &n in normals {
+                    writeln!(out, "    {},", point_to_toml(n)).unwrap();
+                }
+                writeln!(out, "]").unwrap();
+            }
+        }
+
+        out
+    }
+
+    pub fn from_toml(text: &str) -> Result<Scene, Box<error::Error>> {
+        let mut scene = Scene::new();
+        let mut section = String::new();
+        let mut lines = text.lines();
+
+        while let Some(line) = lines.next() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() { continue }
+
+            // Section headers.
+            if line.starts_with("[[") && line.ends_with("]]") {
+                section = line[2 .. line.len() - 2].trim().to_string();
+                match section.as_str() {
+                    "lights" => scene.lights.push(
+                        LightSource { position: pt![0., 0., 0.] }
+                    ),
+                    "meshes" => scene.meshes.push(
+                        (Mesh::new(Vec::new()), Transform::identity())
+                    ),
+                    _ => return Err(From::from(format!(
+                        "unknown scene section: {}",
+                        section
+                    ))),
+                }
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1 .. line.len() - 1].trim().to_string();
+                if section != "camera" {
+                    return Err(From::from(format!(
+                        "unknown scene section: {}",
+                        section
+                    )));
+                }
+                continue;
+            }
+
+            // Key-value pairs. Arrays may continue over several lines.
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap().trim();
+            let mut value = match parts.next() {
+                Some(v) => v.trim().to_string(),
+                None => return Err(From::from(format!(
+                    "expected key = value: {}",
+                    line
+                ))),
+            };
+            while bracket_depth(&value) > 0 {
+                match lines.next() {
+                    Some(more) =>
+                        value.push_str(more.split('#').next().unwrap()),
+                    None => return Err(From::from(format!(
+                        "unterminated array for {}",
+                        key
+                    ))),
+                }
+            }
+            let value = parse_value(&value)?;
+
+            match (section.as_str(), key) {
+                ("", "ambient") => scene.ambient = single(key, &value)?,
+                ("camera", "transform") =>
+                    scene.camera.transform = parse_transform(&value)?,
+                ("lights", "position") => {
+                    let light = scene.lights.last_mut().unwrap();
+                    light.position = parse_point(&value)?;
+                },
+                ("meshes", "transform") => {
+                    let mesh = scene.meshes.last_mut().unwrap();
+                    mesh.1 = parse_transform(&value)?;
+                },
+                ("meshes", "triangles") => {
+                    let mesh = scene.meshes.last_mut().unwrap();
+                    mesh.0.triangles = parse_triangles(&value)?;
+                },
+                ("meshes", "normals") => {
+                    let normals = match rows(&value) {
+                        Some(rows) => rows.iter()
+                            .map(parse_point)
+                            .collect::<Result<Vec<_>, _>>()?,
+                        None => return Err(From::from(
+                            "normals must be an array"
+                        )),
+                    };
+                    let mesh = scene.meshes.last_mut().unwrap();
+                    mesh.0.normals = Some(normals);
+                },
+                _ => return Err(From::from(format!(
+                    "unknown scene key: {}",
+                    key
+                ))),
+            }
+        }
+
+        // Meshes need one normal per triangle, whichever key came first.
+        for mesh in &scene.meshes {
+            if let Some(ref normals) = mesh.0.normals {
+                if normals.len() != mesh.0.triangles.len() {
+                    return Err(From::from(
+                        "meshes need one normal per triangle"
+                    ));
+                }
+            }
+        }
+
+        Ok(scene)
+    }
+}
+
+// TOML spells NaN `nan`, and a float needs a decimal point or exponent
+// (`{:?}` keeps one, along with every digit needed to read it back).
+fn toml_number(c: f64) -> String {
+    if c.is_nan() {
+        "nan".to_string()
+    } else {
+        format!("{:?}", c)
+    }
+}
+
+fn point_to_toml(p: Point) -> String {
+    format!(
+        "[{}, {}, {}]",
+        toml_number(p.x),
+        toml_number(p.y),
+        toml_number(p.z)
+    )
+}
+
+fn transform_to_toml(t: Transform) -> String {
+    let rows: Vec<String> = t.to_array().iter()
+        .map(|row| {
+            let row: Vec<String> = row.iter().map(|&c| toml_number(c))
+                .collect();
+            format!("[{}]", row.join(", "))
+        })
+        .collect();
+    format!("[{}]", rows.join(", "))
+}
+
+fn bracket_depth(s: &str) -> i64 {
+    s.chars().fold(0, |depth, c| match c {
+        '[' => depth + 1,
+        ']' => depth - 1,
+        _   => depth,
+    })
+}
+
+// A value on the right of `=`: a number or an array of values.
+enum Value {
+    Number(f64),
+    Array(Vec<Value>),
+}
+
+fn parse_value(text: &str) -> Result<Value, Box<error::Error>> {
+    let mut rest = text;
+    let value = parse_next_value(&mut rest)?;
+    if !rest.trim().is_empty() {
+        return Err(From::from(format!("unexpected {} after value", rest)));
+    }
+    Ok(value)
+}
+
+// Parses the value at the start of `rest` and advances past it. Arrays may
+// end with a trailing comma.
+fn parse_next_value(rest: &mut &str) -> Result<Value, Box<error::Error>> {
+    *rest = rest.trim_start();
+    if !rest.starts_with('[') {
+        let end = rest.find(&['[', ']', ','][..])
+            .unwrap_or(rest.len());
+        let number = rest[.. end].trim().parse::<f64>()?;
+        *rest = &rest[end ..];
+        return Ok(Value::Number(number));
+    }
+
+    *rest = &rest[1 ..];
+    let mut items = Vec::new();
+    loop {
+        *rest = rest.trim_start();
+        if rest.starts_with(']') { break }
+        items.push(parse_next_value(rest)?);
+        *rest = rest.trim_start();
+        if rest.starts_with(',') {
+            *rest = &rest[1 ..];
+        } else if !rest.starts_with(']') {
+            return Err(From::from("expected , or ] in array"));
+        }
+    }
+    *rest = &rest[1 ..];
+    Ok(Value::Array(items))
+}
+
+// The numbers in an array of exactly `len` numbers.
+fn numbers(value: &Value, len: usize) -> Option<Vec<f64>> {
+    match *value {
+        Value::Array(ref items) if items.len() == len => items.iter()
+            .map(|item| match *item {
+                Value::Number(n) => Some(n),
+                Value::Array(_) => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+fn rows(value: &Value) -> Option<&[Value]> {
+    match *value {
+        Value::Array(ref items) => Some(items),
+        Value::Number(_) => None,
+    }
+}
+
+fn single(key: &str, value: &Value) -> Result<f64, Box<error::Error>> {
+    match *value {
+        Value::Number(n) => Ok(n),
+        Value::Array(_) =>
+            Err(From::from(format!("{} must be a single number", key))),
+    }
+}
+
+fn parse_point(value: &Value) -> Result<Point, Box<error::Error>> {
+    match numbers(value, 3) {
+        Some(c) => Ok(pt![c[0], c[1], c[2]]),
+        None => Err(From::from("points need 3 coords")),
+    }
+}
+
+fn parse_transform(value: &Value) -> Result<Transform, Box<error::Error>> {
+    let rows = match rows(value) {
+        Some(rows) if rows.len() == 4 => rows,
+        _ => return Err(From::from("transforms need 4 rows")),
+    };
+    let mut data = [[0.; 4]; 4];
+    for (i, row) in rows.iter().enumerate() {
+        match numbers(row, 4) {
+            Some(row) => data[i].copy_from_slice(&row),
+            None => return Err(From::from("transform rows need 4 entries")),
+        }
+    }
+    Ok(Transform::from_array(data))
+}
+
+fn parse_triangles(value: &Value)
+    -> Result<Vec<Triangle>, Box<error::Error>>
+{
+    let rows = match rows(value) {
+        Some(rows) => rows,
+        None => return Err(From::from("triangles must be an array")),
+    };
+    rows.iter()
+        .map(|row| match numbers(row, 9) {
+            Some(c) => Ok(trigon![
+                pt![c[0], c[1], c[2]],
+                pt![c[3], c[4], c[5]],
+                pt![c[6], c[7], c[8]]
+            ]),
+            None => Err(From::from("triangles need 9 coords each")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn sample_scene() -> Scene {
+        let mut scene = Scene::new();
+        scene.ambient = 0.25;
+        scene.camera.transform = Transform::translate(pt![0., -1.5, 10.]);
+        scene.lights.push(LightSource { position: pt![1., 2., -3.] });
+        scene.lights.push(LightSource { position: pt![0.1, 0., 1e-3] });
+        let tris = vec![
+            trigon![pt![0., 0., 0.], pt![1., 0., 0.], pt![0., 1., 0.]],
+            trigon![pt![-1., 2.5, 3.], pt![4., 5., 6.], pt![7., 8., 1. / 3.]],
+        ];
+        scene.meshes.push((Mesh::new(tris), Transform::rotate_y(0.5)));
+        scene.meshes.push((Mesh::new(Vec::new()), Transform::identity()));
+        let tri = trigon![pt![0., 0., 1.], pt![1., 0., 1.], pt![0., 1., 1.]];
+        let mesh = Mesh::with_normals(vec![tri], vec![pt![0., 0., -1.]]);
+        scene.meshes.push((mesh, Transform::scale_uniform(2.)));
+        scene
+    }
+
+    fn assert_same(a: &Scene, b: &Scene) {
+        let coords = |p: Point| (p.x, p.y, p.z);
+        assert_eq!(a.ambient, b.ambient);
+        assert_eq!(
+            a.camera.transform.to_array(),
+            b.camera.transform.to_array()
+        );
+        assert_eq!(a.lights.len(), b.lights.len());
+        for (la, lb) in a.lights.iter().zip(b.lights.iter()) {
+            assert_eq!(coords(la.position), coords(lb.position));
+        }
+        assert_eq!(a.meshes.len(), b.meshes.len());
+        for (ma, mb) in a.meshes.iter().zip(b.meshes.iter()) {
+            assert_eq!(ma.1.to_array(), mb.1.to_array());
+            assert_eq!(ma.0.triangles.len(), mb.0.triangles.len());
+            let normals = |m: &Mesh| m.normals.as_ref()
+                .map(|ns| ns.iter().map(|&n| coords(n)).collect::<Vec<_>>());
+            assert_eq!(normals(&ma.0), normals(&mb.0));
+            let pairs = ma.0.triangles.iter().zip(mb.0.triangles.iter());
+            for (ta, tb) in pairs {
+                for (&pa, &pb) in ta.to_arr().iter().zip(tb.to_arr().iter()) {
+                    assert_eq!(coords(pa), coords(pb));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let scene = sample_scene();
+        let loaded = Scene::from_toml(&scene.to_toml()).unwrap();
+        assert_same(&scene, &loaded);
+
+        let empty = Scene::from_toml(&Scene::default().to_toml()).unwrap();
+        assert_same(&Scene::new(), &empty);
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = env::temp_dir().join("rusterize_scene_round_trip.toml");
+        let scene = sample_scene();
+        scene.save(&path).unwrap();
+        let loaded = Scene::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_same(&scene, &loaded.unwrap());
+    }
+
+    #[test]
+    fn rejects_unknown_sections_and_keys() {
+        assert!(Scene::from_toml("[lamps]").is_err());
+        assert!(Scene::from_toml("fov = 90").is_err());
+        assert!(Scene::from_toml("[[meshes]]\ntriangles = [1, 2]").is_err());
+    }
+
+    #[test]
+    fn rejects_misshapen_arrays() {
+        let bad = |text: &str| Scene::from_toml(text).is_err();
+        let flat = vec!["1"; 16].join(", ");
+        assert!(bad(&format!("[camera]\ntransform = [{}]", flat)));
+        assert!(bad("[camera]\ntransform = [[1, 0, 0], [0, 1, 0, 0, 0], \
+                     [0, 0, 1, 0], [0, 0, 0, 1]]"));
+        assert!(bad("ambient = [0.5]"));
+        assert!(bad("ambient = 0.5]"));
+        assert!(bad("[[lights]]\nposition = [1, [2], 3]"));
+        assert!(bad("[[lights]]\nposition = [1, , 2, 3]"));
+        assert!(bad("[[lights]]\nposition = [1 2 3]"));
+    }
+
+    #[test]
+    fn rejects_a_normal_count_mismatch() {
+        let mesh = "[[meshes]]\n\
+                    triangles = [[0, 0, 0, 1, 0, 0, 0, 1, 0]]\n";
+        let ok = format!("{}normals = [[0, 0, 1]]", mesh);
+        assert!(Scene::from_toml(&ok).is_ok());
+        let short = format!("{}normals = []", mesh);
+        assert!(Scene::from_toml(&short).is_err());
+        let long = "[[meshes]]\nnormals = [[0, 0, 1]]";
+        assert!(Scene::from_toml(long).is_err());
+        let flat = format!("{}normals = [0, 0, 1]", mesh);
+        assert!(Scene::from_toml(&flat).is_err());
+    }
+
+    #[test]
+    fn writes_toml_floats() {
+        let mut scene = Scene::new();
+        scene.ambient = 1e300;
+        scene.lights.push(LightSource {
+            position: pt![f64::NAN, f64::INFINITY, 1.],
+        });
+        let text = scene.to_toml();
+        assert!(text.contains("ambient = 1e300\n"));
+        assert!(text.contains("position = [nan, inf, 1.0]\n"));
+
+        let loaded = Scene::from_toml(&text).unwrap();
+        assert_eq!(loaded.ambient, 1e300);
+        let p = loaded.lights[0].position;
+        assert!(p.x.is_nan());
+        assert_eq!((p.y, p.z), (f64::INFINITY, 1.));
+    }
+}
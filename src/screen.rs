@@ -1,5 +1,6 @@
 use sdl2;
 use sdl2::pixels::PixelFormatEnum;
+use sdl2::video::FullscreenType;
 
 use std::error;
 
@@ -11,8 +12,19 @@ pub trait Screen {
     fn display_texture(&mut self, texture: &Texture)
         -> Result<(), Box<error::Error>>;
 
+    fn resize(&mut self, w: Dimension, h: Dimension)
+        -> Result<(), Box<error::Error>>;
+
     fn width (&self) -> Dimension;
     fn height(&self) -> Dimension;
+
+    fn size(&self) -> (Dimension, Dimension) {
+        (self.width(), self.height())
+    }
+
+    fn aspect_ratio(&self) -> f64 {
+        self.width() as f64 / self.height() as f64
+    }
 }
 
 
@@ -40,17 +52,130 @@ impl Screen for TextScreen {
         Ok(())
     }
 
+    fn resize(&mut self, w: Dimension, h: Dimension)
+        -> Result<(), Box<error::Error>>
+    {
+        self.w = w;
+        self.h = h;
+        Ok(())
+    }
+
     fn width (&self) -> Dimension { self.w }
     fn height(&self) -> Dimension { self.h }
 }
 
 
+// Byte layout of each pixel written by a `SliceScreen`. Rgba writes an
+// opaque alpha.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SliceFormat {
+    Rgb,
+    Rgba,
+}
+
+impl SliceFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            SliceFormat::Rgb  => 3,
+            SliceFormat::Rgba => 4,
+        }
+    }
+}
+
+// Writes frames into a caller-owned pixel buffer, such as one exposed by a
+// GUI toolkit. Rows start `stride` bytes apart.
+pub struct SliceScreen<'a> {
+    buf: &'a mut [u8],
+    w: Dimension,
+    h: Dimension,
+    stride: usize,
+    format: SliceFormat,
+}
+
+impl<'a> SliceScreen<'a> {
+    pub fn new(
+        buf: &'a mut [u8],
+        w: Dimension,
+        h: Dimension,
+        stride: usize,
+        format: SliceFormat
+    )
+        -> Result<SliceScreen<'a>, Box<error::Error>>
+    {
+        check_slice_size(buf.len(), w, h, stride, format)?;
+        Ok(SliceScreen {
+            buf: buf,
+            w: w,
+            h: h,
+            stride: stride,
+            format: format,
+        })
+    }
+}
+
+impl<'a> Screen for SliceScreen<'a> {
+    fn display_texture(&mut self, texture: &Texture)
+        -> Result<(), Box<error::Error>>
+    {
+        assert!(texture.w == self.w && texture.h == self.h);
+        let bpp = self.format.bytes_per_pixel();
+        let rows = self.buf
+            .chunks_mut(self.stride.max(1))
+            .zip(texture.rows());
+        for (out_row, row) in rows {
+            let out_pixels = out_row.chunks_mut(bpp).zip(row.iter());
+            for (out, &(r, g, b)) in out_pixels {
+                out[0] = r;
+                out[1] = g;
+                out[2] = b;
+                if bpp == 4 { out[3] = 0xff; }
+            }
+        }
+        Ok(())
+    }
+
+    // The buffer cannot grow, so this fails if the new size does not fit.
+    fn resize(&mut self, w: Dimension, h: Dimension)
+        -> Result<(), Box<error::Error>>
+    {
+        check_slice_size(self.buf.len(), w, h, self.stride, self.format)?;
+        self.w = w;
+        self.h = h;
+        Ok(())
+    }
+
+    fn width (&self) -> Dimension { self.w }
+    fn height(&self) -> Dimension { self.h }
+}
+
+fn check_slice_size(
+    len: usize,
+    w: Dimension,
+    h: Dimension,
+    stride: usize,
+    format: SliceFormat
+)
+    -> Result<(), Box<error::Error>>
+{
+    if w as usize * format.bytes_per_pixel() > stride {
+        return Err(From::from("stride is too small for the width"));
+    }
+    if h as usize * stride > len {
+        return Err(From::from("buffer is too small for the screen"));
+    }
+    Ok(())
+}
+
+
 #[allow(dead_code)]
 pub struct GraphicalScreen<'a> {
     w: Dimension,
     h: Dimension,
     sdl_renderer: sdl2::render::Renderer<'a>,
     texture: sdl2::render::Texture,
+    fullscreen: bool,
+    // Staging buffer for the hardware target path, reused across frames.
+    upload_buf: Option<Vec<u8>>,
 }
 
 #[allow(dead_code)]
@@ -62,25 +187,77 @@ impl<'a> GraphicalScreen<'a> {
         sdl_context: &sdl2::Sdl
     )
         -> Result<GraphicalScreen<'a>, Box<error::Error>>
+    {
+        GraphicalScreen::build(name, w, h, sdl_context, false)
+    }
+
+    // Uploads each frame to a static texture with SDL_UpdateTexture instead
+    // of locking a streaming one, converting through a buffer allocated once.
+    pub fn with_hardware_target(
+        name: &str,
+        w: Dimension,
+        h: Dimension,
+        sdl_context: &sdl2::Sdl
+    )
+        -> Result<GraphicalScreen<'a>, Box<error::Error>>
+    {
+        GraphicalScreen::build(name, w, h, sdl_context, true)
+    }
+
+    fn build(
+        name: &str,
+        w: Dimension,
+        h: Dimension,
+        sdl_context: &sdl2::Sdl,
+        hardware_target: bool
+    )
+        -> Result<GraphicalScreen<'a>, Box<error::Error>>
     {
         // Make an sdl2 window and get the renderer.
         let video_subsystem = sdl_context.video()?;
         let window = video_subsystem
             .window(name, w, h)
             .position_centered()
+            .resizable()
             .opengl()
             .build()?;
         let sdl_renderer = window.renderer().build()?;
-        let texture = sdl_renderer
-            .create_texture_streaming(PixelFormatEnum::RGB24, w, h)?;
+        let texture = create_texture(&sdl_renderer, w, h, hardware_target)?;
+        let upload_buf = if hardware_target {
+            Some(vec![0; 3 * w as usize * h as usize])
+        } else {
+            None
+        };
 
         Ok(GraphicalScreen {
             w: w,
             h: h,
             sdl_renderer: sdl_renderer,
             texture: texture,
+            fullscreen: false,
+            upload_buf: upload_buf,
         })
     }
+
+    pub fn is_fullscreen(&self) -> bool { self.fullscreen }
+
+    // Uses desktop fullscreen, so the window takes the display's resolution
+    // and a resize event follows.
+    pub fn set_fullscreen(&mut self, fullscreen: bool)
+        -> Result<(), Box<error::Error>>
+    {
+        let mode = if fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Off
+        };
+        match self.sdl_renderer.window_mut() {
+            Some(window) => window.set_fullscreen(mode)?,
+            None => return Err(From::from("renderer has no window")),
+        }
+        self.fullscreen = fullscreen;
+        Ok(())
+    }
 }
 
 impl<'a> Screen for GraphicalScreen<'a> {
@@ -88,20 +265,117 @@ impl<'a> Screen for GraphicalScreen<'a> {
         -> Result<(), Box<error::Error>>
     {
         assert!(texture.w == self.w && texture.h == self.h);
-        self.texture.with_lock(None, |buf: &mut [u8], _: usize| {
+        let write_pixels = |buf: &mut [u8]| {
             for i in 0 .. texture.pixels.len() {
                 let (r,g,b) = texture.pixels[i];
                 buf[3 * i]     = r;
                 buf[3 * i + 1] = g;
                 buf[3 * i + 2] = b;
             }
-        })?;
+        };
+        match self.upload_buf {
+            Some(ref mut buf) => {
+                write_pixels(buf);
+                self.texture.update(None, buf, 3 * self.w as usize)?;
+            },
+            None => {
+                self.texture.with_lock(None, |buf: &mut [u8], _: usize| {
+                    write_pixels(buf)
+                })?;
+            },
+        }
 
         self.sdl_renderer.copy(&self.texture, None, None)?;
         self.sdl_renderer.present();
         Ok(())
     }
 
+    fn resize(&mut self, w: Dimension, h: Dimension)
+        -> Result<(), Box<error::Error>>
+    {
+        let hardware_target = self.upload_buf.is_some();
+        self.texture =
+            create_texture(&self.sdl_renderer, w, h, hardware_target)?;
+        if let Some(ref mut buf) = self.upload_buf {
+            buf.resize(3 * w as usize * h as usize, 0);
+        }
+        self.w = w;
+        self.h = h;
+        Ok(())
+    }
+
     fn width (&self) -> Dimension { self.w }
     fn height(&self) -> Dimension { self.h }
 }
+
+fn create_texture(
+    sdl_renderer: &sdl2::render::Renderer,
+    w: Dimension,
+    h: Dimension,
+    hardware_target: bool
+)
+    -> Result<sdl2::render::Texture, Box<error::Error>>
+{
+    let format = PixelFormatEnum::RGB24;
+    let texture = if hardware_target {
+        sdl_renderer.create_texture_static(format, w, h)?
+    } else {
+        sdl_renderer.create_texture_streaming(format, w, h)?
+    };
+    Ok(texture)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pixel;
+
+    fn two_by_two() -> Texture {
+        let mut t = Texture::new(2, 2);
+        t.pixels = vec![pixel::RED, (1, 2, 3), pixel::BLUE, pixel::WHITE];
+        t
+    }
+
+    #[test]
+    fn slice_screen_writes_rgb_rows() {
+        // Two padding bytes end each row and are left alone.
+        let mut buf = [0xaa; 16];
+        {
+            let mut screen =
+                SliceScreen::new(&mut buf, 2, 2, 8, SliceFormat::Rgb).unwrap();
+            screen.display_texture(&two_by_two()).unwrap();
+        }
+        assert_eq!(buf, [
+            255, 0, 0,     1, 2, 3,        0xaa, 0xaa,
+            0, 0, 255,     255, 255, 255,  0xaa, 0xaa,
+        ]);
+    }
+
+    #[test]
+    fn slice_screen_writes_opaque_rgba() {
+        let mut buf = [0; 16];
+        {
+            let mut screen =
+                SliceScreen::new(&mut buf, 2, 2, 8, SliceFormat::Rgba).unwrap();
+            screen.display_texture(&two_by_two()).unwrap();
+        }
+        assert_eq!(&buf[.. 8], &[255, 0, 0, 255, 1, 2, 3, 255]);
+        assert_eq!(&buf[8 ..], &[0, 0, 255, 255, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn slice_screen_checks_its_size() {
+        let mut buf = [0; 12];
+        let (rgb, rgba) = (SliceFormat::Rgb, SliceFormat::Rgba);
+        assert!(SliceScreen::new(&mut buf, 2, 2, 5, rgb).is_err());
+        assert!(SliceScreen::new(&mut buf, 2, 3, 6, rgb).is_err());
+        assert!(SliceScreen::new(&mut buf, 2, 2, 6, rgba).is_err());
+
+        let mut screen = SliceScreen::new(&mut buf, 2, 2, 6, rgb).unwrap();
+        assert!(screen.resize(2, 3).is_err());
+        assert_eq!(screen.size(), (2, 2));
+        assert!(screen.resize(1, 2).is_ok());
+        assert_eq!(screen.size(), (1, 2));
+    }
+}
@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+use std::error;
+use std::f32;
 use std::f64;
 use std::fmt;
 use std::fmt::Display;
@@ -8,21 +11,256 @@ use types::*;
 use utils::*;
 
 
-pub struct Texture {
+// The most pixels `try_new` agrees to allocate, 8192 x 8192.
+pub const MAX_TEXTURE_PIXELS: usize = 1 << 26;
+
+const BAYER_4X4: [[u8; 4]; 4] = [
+    [ 0,  8,  2, 10],
+    [12,  4, 14,  6],
+    [ 3, 11,  1,  9],
+    [15,  7, 13,  5],
+];
+
+mod private {
+    pub trait Sealed {}
+
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+// Storage type of a texture's depth buffer. Depths are computed as `Coord`
+// and converted on write, so `f32` halves the buffer at a loss of precision.
+pub trait DepthValue: private::Sealed + Copy + PartialOrd {
+    fn from_coord(z: Coord) -> Self;
+    fn to_coord(self) -> Coord;
+    fn infinity() -> Self;
+}
+
+impl DepthValue for f32 {
+    fn from_coord(z: Coord) -> f32 { z as f32 }
+    fn to_coord(self) -> Coord { self as Coord }
+    fn infinity() -> f32 { f32::INFINITY }
+}
+
+impl DepthValue for f64 {
+    fn from_coord(z: Coord) -> f64 { z }
+    fn to_coord(self) -> Coord { self }
+    fn infinity() -> f64 { f64::INFINITY }
+}
+
+// How `Texture::sample_mip` filters. Nearest and Bilinear sample the mip
+// level closest to the requested detail; Trilinear blends the two levels
+// around it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FilterMode {
+    Nearest,
+    Bilinear,
+    Trilinear,
+}
+
+// Comparison of an incoming depth against the stored one; the pixel is
+// written when it holds. `Always` disables depth testing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DepthTest {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    Always,
+    Never,
+}
+
+impl DepthTest {
+    fn passes<D: DepthValue>(self, z: D, stored: D) -> bool {
+        match self {
+            DepthTest::Less         => z <  stored,
+            DepthTest::LessEqual    => z <= stored,
+            DepthTest::Greater      => z >  stored,
+            DepthTest::GreaterEqual => z >= stored,
+            DepthTest::Equal        => z == stored,
+            DepthTest::Always       => true,
+            DepthTest::Never        => false,
+        }
+    }
+}
+
+// How a written color combines with the one already stored, passed to the
+// matching `pixel::blend_*` function as (stored, written).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    Replace,
+    Additive,
+    Multiply,
+    Screen,
+    Overlay,
+}
+
+impl BlendMode {
+    fn blend(self, stored: Pixel, color: Pixel) -> Pixel {
+        match self {
+            BlendMode::Replace  => color,
+            BlendMode::Additive => pixel::blend_additive(stored, color),
+            BlendMode::Multiply => pixel::blend_multiply(stored, color),
+            BlendMode::Screen   => pixel::blend_screen(stored, color),
+            BlendMode::Overlay  => pixel::blend_overlay(stored, color),
+        }
+    }
+
+    // Like `blend`, on unclamped linear channels.
+    fn blend_hdr(self, stored: HdrPixel, color: HdrPixel) -> HdrPixel {
+        let f = |a: f32, b: f32| match self {
+            BlendMode::Replace  => b,
+            BlendMode::Additive => a + b,
+            BlendMode::Multiply => a * b,
+            BlendMode::Screen   => a + b - a * b,
+            BlendMode::Overlay  => {
+                if a < 0.5 {
+                    2. * a * b
+                } else {
+                    1. - 2. * (1. - a) * (1. - b)
+                }
+            },
+        };
+        (f(stored.0, color.0), f(stored.1, color.1), f(stored.2, color.2))
+    }
+}
+
+// A linear color in a `TextureHdr`, where 1.0 is the brightest 8-bit value.
+pub type HdrPixel = (f32, f32, f32);
+
+fn to_hdr((r, g, b): Pixel) -> HdrPixel {
+    (r as f32 / 255., g as f32 / 255., b as f32 / 255.)
+}
+
+#[derive(Clone)]
+pub struct Texture<D: DepthValue = f64> {
     pub w: Dimension,
     pub h: Dimension,
     pub pixels: Vec<Pixel>,
-    z_buffer:   Vec<Coord>,
+    z_buffer:   Vec<D>,
+    depth_test: DepthTest,
+    blend_mode: BlendMode,
+    scissor: Option<(PixCoord, PixCoord, Dimension, Dimension)>,
+    // Allocated by the first `set_write_id`; None until then.
+    id_buffer: Option<Vec<Option<usize>>>,
+    write_id: Option<usize>,
+    // Allocated by `set_hdr`; mirrors every depth-tested write without
+    // clamping.
+    hdr: Option<TextureHdr>,
 }
 
-impl Texture {
-    pub fn new(w: Dimension, h: Dimension) -> Texture {
+pub type Texture32 = Texture<f32>;
+pub type Texture64 = Texture<f64>;
+
+type RowSlices<'a, D> = (
+    &'a mut [Pixel],
+    &'a mut [D],
+    Option<&'a mut [Option<usize>]>,
+    Option<&'a mut [HdrPixel]>
+);
+
+impl<D: DepthValue> Texture<D> {
+    // Constructor for any depth type, e.g. `Texture32::with_size(w, h)`;
+    // `Texture::new` is the f64 shorthand.
+    pub fn with_size(w: Dimension, h: Dimension) -> Texture<D> {
         let num_pixels = w as usize * h as usize;
         Texture {
             w: w,
             h: h,
             pixels:   vec![pixel::BLACK;  num_pixels],
-            z_buffer: vec![f64::INFINITY; num_pixels],
+            z_buffer: vec![D::infinity(); num_pixels],
+            depth_test: DepthTest::Less,
+            blend_mode: BlendMode::Replace,
+            scissor: None,
+            id_buffer: None,
+            write_id: None,
+            hdr: None,
+        }
+    }
+
+    // Like `with_size`, but None instead of an allocation of more than
+    // `MAX_TEXTURE_PIXELS` pixels.
+    pub fn try_with_size(w: Dimension, h: Dimension) -> Option<Texture<D>> {
+        if w as u64 * h as u64 > MAX_TEXTURE_PIXELS as u64 { return None }
+        Some(Texture::with_size(w, h))
+    }
+
+    pub fn set_depth_test(&mut self, mode: DepthTest) {
+        self.depth_test = mode;
+    }
+
+    pub fn depth_test(&self) -> DepthTest { self.depth_test }
+    pub fn blend_mode(&self) -> BlendMode { self.blend_mode }
+
+    // Applies to every depth-tested write: `set_pixel`, `set_row` and
+    // `fill_region`.
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.blend_mode = mode;
+    }
+
+    // Restricts `set_pixel`, `set_row` and `fill_region` to the rectangle
+    // (x, y, w, h), or lifts the restriction with None.
+    pub fn set_scissor(
+        &mut self,
+        scissor: Option<(PixCoord, PixCoord, Dimension, Dimension)>
+    ) {
+        self.scissor = scissor;
+    }
+
+    // Tags every pixel written from now on with `id`, for `read_id`. Writes
+    // under None clear the tag of the pixels they cover.
+    pub fn set_write_id(&mut self, id: Option<usize>) {
+        if id.is_some() && self.id_buffer.is_none() {
+            self.id_buffer = Some(vec![None; self.pixels.len()]);
+        }
+        self.write_id = id;
+    }
+
+    // Keeps a floating-point copy of the pixels that writes add to without
+    // clamping, so blending can go past white. Enabling copies the current
+    // pixels into it.
+    pub fn set_hdr(&mut self, enabled: bool) {
+        self.hdr = if enabled {
+            Some(TextureHdr::from_pixels(self.w, self.h, &self.pixels))
+        } else {
+            None
+        };
+    }
+
+    pub fn hdr(&self) -> Option<&TextureHdr> { self.hdr.as_ref() }
+    pub fn hdr_mut(&mut self) -> Option<&mut TextureHdr> { self.hdr.as_mut() }
+
+    // Reloads the HDR copy from the pixels after they were written directly,
+    // e.g. through `pixels_mut_row_slice`.
+    pub fn sync_hdr(&mut self) {
+        if self.hdr.is_some() { self.set_hdr(true) }
+    }
+
+    // A copy whose pixels are the tone mapped HDR copy, or a plain copy
+    // without one. Depths are kept.
+    pub fn tonemapped(&self) -> Texture<D> {
+        let mut out = self.clone();
+        if let Some(hdr) = out.hdr.take() {
+            out.pixels = hdr.tonemap_reinhard().pixels;
+        }
+        out
+    }
+
+    // The writable area as (x0, y0, x1, y1), with x1 and y1 exclusive.
+    fn clip_rect(&self) -> (i64, i64, i64, i64) {
+        let (w, h) = (self.w as i64, self.h as i64);
+        match self.scissor {
+            Some((x, y, sw, sh)) => {
+                let (x, y) = (x as i64, y as i64);
+                (
+                    x.max(0),
+                    y.max(0),
+                    (x + sw as i64).min(w),
+                    (y + sh as i64).min(h)
+                )
+            },
+            None => (0, 0, w, h),
         }
     }
 
@@ -33,8 +271,9 @@ impl Texture {
         z: Coord,
         color: Pixel
     ) {
-        if x < 0 || y < 0 { return }
-        if self.w < x as Dimension || self.h < y as Dimension { return }
+        let (x0, y0, x1, y1) = self.clip_rect();
+        let (px, py) = (x as i64, y as i64);
+        if px < x0 || py < y0 || px >= x1 || py >= y1 { return }
         self.set_pixel_nocheck(x, y, z, color)
     }
 
@@ -46,9 +285,52 @@ impl Texture {
         color: Pixel
     ) {
         let index = y as usize * self.w as usize + x as usize;
-        if z >= self.z_buffer[index] { return }
+        let z = D::from_coord(z);
+        if !self.depth_test.passes(z, self.z_buffer[index]) { return }
         self.z_buffer[index] = z;
-        self.pixels[index]   = color;
+        self.pixels[index]   = self.blend_mode.blend(self.pixels[index], color);
+        if let Some(ref mut ids) = self.id_buffer { ids[index] = self.write_id }
+        if let Some(ref mut hdr) = self.hdr {
+            let p = &mut hdr.pixels[index];
+            *p = self.blend_mode.blend_hdr(*p, to_hdr(color));
+        }
+    }
+
+    // Mixes `color`, combined by the blend mode, into the pixel by `alpha` in
+    // [0, 1]. Equal depths pass the depth test too, so overlapping overlays
+    // at the same depth accumulate.
+    pub fn blend_pixel(
+        &mut self,
+        x: PixCoord,
+        y: PixCoord,
+        z: Coord,
+        color: Pixel,
+        alpha: f64
+    ) {
+        let (x0, y0, x1, y1) = self.clip_rect();
+        let (px, py) = (x as i64, y as i64);
+        if px < x0 || py < y0 || px >= x1 || py >= y1 { return }
+
+        let index = y as usize * self.w as usize + x as usize;
+        let z = D::from_coord(z);
+        let stored = self.z_buffer[index];
+        if !self.depth_test.passes(z, stored) && z != stored { return }
+        self.z_buffer[index] = z;
+        let under = self.pixels[index];
+        let alpha = clamp(alpha, 0., 1.);
+        let blended = self.blend_mode.blend(under, color);
+        self.pixels[index] = pixel::lerp(under, blended, alpha);
+        if let Some(ref mut ids) = self.id_buffer { ids[index] = self.write_id }
+        if let Some(ref mut hdr) = self.hdr {
+            let p = &mut hdr.pixels[index];
+            let (r, g, b) = self.blend_mode.blend_hdr(*p, to_hdr(color));
+            let a = alpha as f32;
+            *p = (
+                p.0 + (r - p.0) * a,
+                p.1 + (g - p.1) * a,
+                p.2 + (b - p.2) * a
+            );
+        }
     }
 
     pub fn set_row(
@@ -60,53 +342,1308 @@ impl Texture {
         z2: Coord,
         color: Pixel
     ) {
-        if y  < 0 || (           y as Dimension >= self.h) { return }
-        if x2 < 0 || (x1 > 0 && x1 as Dimension >= self.w) { return }
+        let (cx0, cy0, cx1, cy1) = self.clip_rect();
+        if (y as i64) < cy0 || y as i64 >= cy1 { return }
+        if (x2 as i64) < cx0 || x1 as i64 >= cx1 || cx1 <= cx0 { return }
 
-        let start = clamp(x1, 0, (self.w - 1) as PixCoord);
-        let end   = clamp(x2, 0, (self.w - 1) as PixCoord);
+        let start = clamp(x1 as i64, cx0, cx1 - 1) as PixCoord;
+        let end   = clamp(x2 as i64, cx0, cx1 - 1) as PixCoord;
 
         if x2 <= x1 { return }
+        let depth_test = self.depth_test;
+        let blend_mode = self.blend_mode;
+        let write_id = self.write_id;
+        let hdr_color = to_hdr(color);
+        let (pixels, depths, mut ids, mut hdr) = self.row_slices_mut(y);
         for x in start .. end + 1 {
             let t = ((x - x1) as f64) / ((x2 - x1) as f64);
-            let z = z1 * (1. - t) + z2 * t;
-            self.set_pixel_nocheck(x, y, z, color);
+            let z = D::from_coord(z1 * (1. - t) + z2 * t);
+            let i = x as usize;
+            if !depth_test.passes(z, depths[i]) { continue }
+            depths[i] = z;
+            pixels[i] = blend_mode.blend(pixels[i], color);
+            if let Some(ref mut ids) = ids { ids[i] = write_id }
+            if let Some(ref mut hdr) = hdr {
+                hdr[i] = blend_mode.blend_hdr(hdr[i], hdr_color);
+            }
+        }
+    }
+
+    // The pixels of row `y`, or None if it is outside the texture. Writes
+    // bypass the depth buffer.
+    pub fn pixels_mut_row_slice(&mut self, y: PixCoord)
+        -> Option<&mut [Pixel]>
+    {
+        if y < 0 || y as Dimension >= self.h { return None }
+        Some(self.row_slices_mut(y).0)
+    }
+
+    // Pixels, depths, ids and HDR colors of row `y`, which must be inside
+    // the texture.
+    fn row_slices_mut<'a>(&'a mut self, y: PixCoord) -> RowSlices<'a, D> {
+        let w = self.w as usize;
+        let start = y as usize * w;
+        (
+            &mut self.pixels[start .. start + w],
+            &mut self.z_buffer[start .. start + w],
+            self.id_buffer.as_mut().map(|ids| &mut ids[start .. start + w]),
+            self.hdr.as_mut().map(|hdr| &mut hdr.pixels[start .. start + w])
+        )
+    }
+
+    // Fills the rectangle with its top-left corner at (x, y) on top of
+    // everything else. Parts outside the texture are skipped.
+    pub fn fill_region(
+        &mut self,
+        x: PixCoord,
+        y: PixCoord,
+        w: Dimension,
+        h: Dimension,
+        color: Pixel
+    ) {
+        self.fill_region_with_depth(x, y, w, h, f64::NEG_INFINITY, color);
+    }
+
+    // Like `fill_region`, but depth tested at `depth`.
+    pub fn fill_region_with_depth(
+        &mut self,
+        x: PixCoord,
+        y: PixCoord,
+        w: Dimension,
+        h: Dimension,
+        depth: Coord,
+        color: Pixel
+    ) {
+        let (cx0, cy0, cx1, cy1) = self.clip_rect();
+        let x_start = (x as i64).max(cx0);
+        let y_start = (y as i64).max(cy0);
+        // Clamped so regions wholly left of the clip rectangle come out
+        // empty instead of wrapping around as usize.
+        let x_end = (x as i64 + w as i64).min(cx1).max(x_start);
+        let y_end = (y as i64 + h as i64).min(cy1);
+        let depth_test = self.depth_test;
+        let blend_mode = self.blend_mode;
+        let write_id = self.write_id;
+        let z = D::from_coord(depth);
+        let hdr_color = to_hdr(color);
+        for py in y_start .. y_end {
+            let (pixels, depths, mut ids, mut hdr) =
+                self.row_slices_mut(py as PixCoord);
+            for px in x_start as usize .. x_end as usize {
+                if !depth_test.passes(z, depths[px]) { continue }
+                depths[px] = z;
+                pixels[px] = blend_mode.blend(pixels[px], color);
+                if let Some(ref mut ids) = ids { ids[px] = write_id }
+                if let Some(ref mut hdr) = hdr {
+                    hdr[px] = blend_mode.blend_hdr(hdr[px], hdr_color);
+                }
+            }
+        }
+    }
+
+    pub fn read_pixel(&self, x: PixCoord, y: PixCoord) -> Option<Pixel> {
+        self.index(x, y).map(|i| self.pixels[i])
+    }
+
+    pub fn read_depth(&self, x: PixCoord, y: PixCoord) -> Option<Coord> {
+        self.index(x, y).map(|i| self.z_buffer[i].to_coord())
+    }
+
+    // Whether a write at depth `z` would pass the depth test at (x, y).
+    // Always false off the texture.
+    pub fn depth_passes(&self, x: PixCoord, y: PixCoord, z: Coord) -> bool {
+        match self.index(x, y) {
+            Some(i) => {
+                self.depth_test.passes(D::from_coord(z), self.z_buffer[i])
+            },
+            None => false,
+        }
+    }
+
+    // The id the pixel was last written under, see `set_write_id`.
+    pub fn read_id(&self, x: PixCoord, y: PixCoord) -> Option<usize> {
+        match (self.index(x, y), self.id_buffer.as_ref()) {
+            (Some(i), Some(ids)) => ids[i],
+            _ => None,
         }
     }
 
+    // Every pixel as (x, y, color, depth), row by row from the top-left.
+    pub fn pixels_with_coords<'a>(&'a self)
+        -> impl Iterator<Item = (PixCoord, PixCoord, Pixel, Coord)> + 'a
+    {
+        let w = self.w.max(1) as usize;
+        self.pixels.iter()
+            .zip(self.z_buffer.iter())
+            .enumerate()
+            .map(move |(i, (&p, &z))| {
+                ((i % w) as PixCoord, (i / w) as PixCoord, p, z.to_coord())
+            })
+    }
+
+    pub fn rows<'a>(&'a self) -> impl Iterator<Item = &'a [Pixel]> + 'a {
+        self.pixels.chunks(self.w.max(1) as usize)
+    }
+
+    fn index(&self, x: PixCoord, y: PixCoord) -> Option<usize> {
+        if x < 0 || y < 0 { return None }
+        if x as Dimension >= self.w || y as Dimension >= self.h { return None }
+        Some(y as usize * self.w as usize + x as usize)
+    }
+
+    // Nearest-neighbor lookup with (0, 0) at the top-left corner and (1, 1) at
+    // the bottom-right. Coordinates outside [0, 1] are clamped to the edge.
+    pub fn sample(&self, u: f64, v: f64) -> Pixel {
+        let x = clamp((u * self.w as f64) as i64, 0, self.w as i64 - 1);
+        let y = clamp((v * self.h as f64) as i64, 0, self.h as i64 - 1);
+        self.pixels[y as usize * self.w as usize + x as usize]
+    }
+
+    // Like `sample`, but blends the four texels nearest to (u, v).
+    pub fn sample_bilinear(&self, u: f64, v: f64) -> Pixel {
+        let x = u * self.w as f64 - 0.5;
+        let y = v * self.h as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |x: f64, y: f64| {
+            let x = clamp(x as i64, 0, self.w as i64 - 1) as usize;
+            let y = clamp(y as i64, 0, self.h as i64 - 1) as usize;
+            self.pixels[y * self.w as usize + x]
+        };
+        let top = pixel::lerp(texel(x0, y0), texel(x0 + 1., y0), fx);
+        let bot = pixel::lerp(texel(x0, y0 + 1.), texel(x0 + 1., y0 + 1.), fx);
+        pixel::lerp(top, bot, fy)
+    }
+
     pub fn set_all_pixels(&mut self, color: Pixel) {
         for i in 0..self.pixels.len() {
             self.pixels[i] = color;
         }
+        if let Some(ref mut hdr) = self.hdr {
+            for p in hdr.pixels.iter_mut() { *p = to_hdr(color) }
+        }
+    }
+
+    // Reallocates the buffers for the new size, leaving the texture cleared.
+    pub fn resize(&mut self, w: Dimension, h: Dimension) {
+        let depth_test = self.depth_test;
+        let blend_mode = self.blend_mode;
+        let scissor = self.scissor;
+        let has_ids = self.id_buffer.is_some();
+        let write_id = self.write_id;
+        let has_hdr = self.hdr.is_some();
+        *self = Texture::with_size(w, h);
+        self.depth_test = depth_test;
+        self.blend_mode = blend_mode;
+        self.scissor = scissor;
+        if has_ids { self.id_buffer = Some(vec![None; self.pixels.len()]) }
+        self.write_id = write_id;
+        self.set_hdr(has_hdr);
     }
 
     pub fn clear(&mut self) {
         for i in 0 .. self.pixels.len() {
             self.pixels[i]   = pixel::BLACK;
-            self.z_buffer[i] = f64::INFINITY;
+            self.z_buffer[i] = D::infinity();
+        }
+        if let Some(ref mut ids) = self.id_buffer {
+            for id in ids.iter_mut() { *id = None }
+        }
+        if let Some(ref mut hdr) = self.hdr { hdr.clear() }
+    }
+
+    // Whether both textures have the same size and no channel of any pixel
+    // differs by more than `tolerance`. Depths are ignored.
+    pub fn compare(&self, other: &Texture<D>, tolerance: u8) -> bool {
+        if self.w != other.w || self.h != other.h { return false }
+        let within = |a: u8, b: u8| {
+            (a as i16 - b as i16).abs() <= tolerance as i16
+        };
+        self.pixels.iter().zip(other.pixels.iter()).all(|(&a, &b)| {
+            within(a.0, b.0) && within(a.1, b.1) && within(a.2, b.2)
+        })
+    }
+
+    // Per-channel absolute differences, stretched so the largest one is
+    // white. Identical textures give black, and textures of different sizes
+    // give None.
+    pub fn diff(&self, other: &Texture<D>) -> Option<Texture> {
+        if self.w != other.w || self.h != other.h { return None }
+        let delta = |a: u8, b: u8| a.max(b) - a.min(b);
+        let deltas: Vec<Pixel> = self.pixels.iter()
+            .zip(other.pixels.iter())
+            .map(|(&a, &b)| {
+                (delta(a.0, b.0), delta(a.1, b.1), delta(a.2, b.2))
+            })
+            .collect();
+        let max = deltas.iter()
+            .map(|&(r, g, b)| r.max(g).max(b))
+            .max()
+            .unwrap_or(0)
+            .max(1) as u32;
+
+        let mut out = Texture::new(self.w, self.h);
+        let stretch = |c: u8| (c as u32 * 255 / max) as u8;
+        for (p, &(r, g, b)) in out.pixels.iter_mut().zip(deltas.iter()) {
+            *p = (stretch(r), stretch(g), stretch(b));
+        }
+        Some(out)
+    }
+
+    // Each pixel averaged with all pixels up to `radius` away along both
+    // axes, as a horizontal then a vertical pass. Edges are clamped.
+    pub fn box_blur(&self, radius: usize) -> Texture<D> {
+        let n = 2 * radius + 1;
+        self.blurred(&vec![1. / n as f32; n])
+    }
+
+    // A Gaussian blur with standard deviation `sigma` in pixels, cut off at
+    // three deviations. Edges are clamped.
+    pub fn gaussian_blur(&self, sigma: f64) -> Texture<D> {
+        if sigma.is_nan() || sigma <= 0. { return self.clone() }
+        let radius = (3. * sigma).ceil() as i64;
+        let weights: Vec<f64> = (-radius .. radius + 1)
+            .map(|i| (-(i * i) as f64 / (2. * sigma * sigma)).exp())
+            .collect();
+        let total: f64 = weights.iter().sum();
+        let kernel: Vec<f32> =
+            weights.iter().map(|&w| (w / total) as f32).collect();
+        self.blurred(&kernel)
+    }
+
+    // Convolves rows then columns with the separable `kernel`.
+    fn blurred(&self, kernel: &[f32]) -> Texture<D> {
+        let (w, h) = (self.w as usize, self.h as usize);
+        let channels: Vec<(f32, f32, f32)> = self.pixels.iter()
+            .map(|&(r, g, b)| (r as f32, g as f32, b as f32))
+            .collect();
+        let pass = convolve_pass(&channels, w, h, 1, w, kernel);
+        let pass = convolve_pass(&pass, h, w, w, 1, kernel);
+
+        let mut out = self.clone();
+        out.hdr = None;
+        let to_u8 = |c: f32| clamp(c.round(), 0., 255.) as u8;
+        for (p, &(r, g, b)) in out.pixels.iter_mut().zip(pass.iter()) {
+            *p = (to_u8(r), to_u8(g), to_u8(b));
+        }
+        out
+    }
+
+    // Resets the depth buffer only, e.g. between a depth pre-pass and the
+    // shading pass.
+    pub fn clear_depth(&mut self) {
+        for z in self.z_buffer.iter_mut() {
+            *z = D::infinity();
+        }
+    }
+
+    // Mirrored left to right.
+    pub fn horizontal_flip(&self) -> Texture<D> {
+        let mut out = self.clone();
+        out.flip_h_mut();
+        out
+    }
+
+    // Mirrored top to bottom.
+    pub fn vertical_flip(&self) -> Texture<D> {
+        let mut out = self.clone();
+        out.flip_v_mut();
+        out
+    }
+
+    // Turned a quarter clockwise; width and height swap.
+    pub fn rotate_90(&self) -> Texture<D> {
+        let h = self.h as usize;
+        self.remap(self.h, self.w, |x, y| (h - 1 - y, x))
+    }
+
+    pub fn rotate_180(&self) -> Texture<D> {
+        let mut out = self.horizontal_flip();
+        out.flip_v_mut();
+        out
+    }
+
+    // Turned a quarter counterclockwise; width and height swap.
+    pub fn rotate_270(&self) -> Texture<D> {
+        let w = self.w as usize;
+        self.remap(self.h, self.w, |x, y| (y, w - 1 - x))
+    }
+
+    // A w x h texture where the pixel at `to(x, y)` is this texture's pixel
+    // at (x, y).
+    fn remap<F>(&self, w: Dimension, h: Dimension, to: F) -> Texture<D>
+        where F: Fn(usize, usize) -> (usize, usize)
+    {
+        let mut out = self.clone();
+        out.w = w;
+        out.h = h;
+        if let Some(ref mut hdr) = out.hdr {
+            hdr.w = w;
+            hdr.h = h;
+        }
+        for y in 0 .. self.h as usize {
+            for x in 0 .. self.w as usize {
+                let (tx, ty) = to(x, y);
+                let from = y * self.w as usize + x;
+                let into = ty * w as usize + tx;
+                out.pixels[into] = self.pixels[from];
+                out.z_buffer[into] = self.z_buffer[from];
+                if let (Some(out_ids), Some(ids)) =
+                    (out.id_buffer.as_mut(), self.id_buffer.as_ref())
+                {
+                    out_ids[into] = ids[from];
+                }
+                if let (Some(out_hdr), Some(hdr)) =
+                    (out.hdr.as_mut(), self.hdr.as_ref())
+                {
+                    out_hdr.pixels[into] = hdr.pixels[from];
+                }
+            }
+        }
+        out
+    }
+
+    pub fn flip_h_mut(&mut self) {
+        let w = self.w.max(1) as usize;
+        for row in self.pixels.chunks_mut(w) { row.reverse() }
+        for row in self.z_buffer.chunks_mut(w) { row.reverse() }
+        if let Some(ref mut ids) = self.id_buffer {
+            for row in ids.chunks_mut(w) { row.reverse() }
+        }
+        if let Some(ref mut hdr) = self.hdr {
+            for row in hdr.pixels.chunks_mut(w) { row.reverse() }
+        }
+    }
+
+    pub fn flip_v_mut(&mut self) {
+        let w = self.w as usize;
+        let h = self.h as usize;
+        for y in 0 .. h / 2 {
+            let (top, bot) = (y * w, (h - 1 - y) * w);
+            for x in 0 .. w {
+                self.pixels.swap(top + x, bot + x);
+                self.z_buffer.swap(top + x, bot + x);
+                if let Some(ref mut ids) = self.id_buffer {
+                    ids.swap(top + x, bot + x);
+                }
+                if let Some(ref mut hdr) = self.hdr {
+                    hdr.pixels.swap(top + x, bot + x);
+                }
+            }
+        }
+    }
+}
+
+impl Texture {
+    pub fn new(w: Dimension, h: Dimension) -> Texture {
+        Texture::with_size(w, h)
+    }
+
+    pub fn try_new(w: Dimension, h: Dimension) -> Option<Texture> {
+        Texture::try_with_size(w, h)
+    }
+
+    pub fn checkerboard(
+        w: Dimension,
+        h: Dimension,
+        cell_size: Dimension,
+        color_a: Pixel,
+        color_b: Pixel
+    )
+        -> Texture
+    {
+        let cell_size = cell_size.max(1);
+        let mut texture = Texture::new(w, h);
+        for y in 0 .. h {
+            for x in 0 .. w {
+                let even = (x / cell_size + y / cell_size) & 1 == 0;
+                texture.pixels[(y * w + x) as usize] =
+                    if even { color_a } else { color_b };
+            }
+        }
+        texture
+    }
+
+    // Fades from `left` in the first column to `right` in the last.
+    pub fn gradient_horizontal(
+        w: Dimension,
+        h: Dimension,
+        left: Pixel,
+        right: Pixel
+    )
+        -> Texture
+    {
+        let mut texture = Texture::new(w, h);
+        let span = (w.max(2) - 1) as f64;
+        for y in 0 .. h {
+            for x in 0 .. w {
+                texture.pixels[(y * w + x) as usize] =
+                    pixel::lerp(left, right, x as f64 / span);
+            }
+        }
+        texture
+    }
+
+    // Fades from `top` in the first row to `bottom` in the last.
+    pub fn gradient_vertical(
+        w: Dimension,
+        h: Dimension,
+        top: Pixel,
+        bottom: Pixel
+    )
+        -> Texture
+    {
+        let mut texture = Texture::new(w, h);
+        let span = (h.max(2) - 1) as f64;
+        for y in 0 .. h {
+            let color = pixel::lerp(top, bottom, y as f64 / span);
+            for x in 0 .. w {
+                texture.pixels[(y * w + x) as usize] = color;
+            }
+        }
+        texture
+    }
+
+    // The full mipmap chain, starting with a copy of this texture as level 0
+    // and halving both sides down to 1x1. Each texel averages the 2x2 block
+    // below it.
+    pub fn generate_mipmaps(&self) -> Vec<Texture> {
+        let mut levels = vec![self.clone()];
+        loop {
+            let next = {
+                let prev = &levels[levels.len() - 1];
+                if prev.w <= 1 && prev.h <= 1 { break }
+                prev.downsample()
+            };
+            levels.push(next);
+        }
+        levels
+    }
+
+    fn downsample(&self) -> Texture {
+        let w = (self.w / 2).max(1);
+        let h = (self.h / 2).max(1);
+        let mut out = Texture::new(w, h);
+        let texel = |x: Dimension, y: Dimension| {
+            let x = x.min(self.w - 1);
+            let y = y.min(self.h - 1);
+            self.pixels[(y * self.w + x) as usize]
+        };
+        for y in 0 .. h {
+            for x in 0 .. w {
+                let block = [
+                    texel(2 * x,     2 * y),
+                    texel(2 * x + 1, 2 * y),
+                    texel(2 * x,     2 * y + 1),
+                    texel(2 * x + 1, 2 * y + 1),
+                ];
+                let mut sum = (0u32, 0u32, 0u32);
+                for &(r, g, b) in &block {
+                    sum.0 += r as u32;
+                    sum.1 += g as u32;
+                    sum.2 += b as u32;
+                }
+                out.pixels[(y * w + x) as usize] = (
+                    ((sum.0 + 2) / 4) as u8,
+                    ((sum.1 + 2) / 4) as u8,
+                    ((sum.2 + 2) / 4) as u8
+                );
+            }
+        }
+        out
+    }
+
+    // Samples a mipmap chain from `generate_mipmaps` at level of detail
+    // `lod`, where 0 is full resolution and each step halves it.
+    pub fn sample_mip(
+        mips: &[Texture],
+        u: f64,
+        v: f64,
+        lod: f64,
+        filter: FilterMode
+    )
+        -> Pixel
+    {
+        if mips.is_empty() { return pixel::BLACK }
+        let max_level = (mips.len() - 1) as f64;
+        let lod = clamp(lod, 0., max_level);
+        match filter {
+            FilterMode::Nearest =>
+                mips[lod.round() as usize].sample(u, v),
+            FilterMode::Bilinear =>
+                mips[lod.round() as usize].sample_bilinear(u, v),
+            FilterMode::Trilinear => {
+                let lower = lod.floor();
+                let upper = (lower + 1.).min(max_level);
+                pixel::lerp(
+                    mips[lower as usize].sample_bilinear(u, v),
+                    mips[upper as usize].sample_bilinear(u, v),
+                    lod - lower
+                )
+            },
+        }
+    }
+
+    pub fn grayscale(&self) -> Texture {
+        let mut out = self.clone();
+        out.hdr = None;
+        for p in out.pixels.iter_mut() {
+            *p = pixel::to_grayscale(*p);
+        }
+        out
+    }
+
+    pub fn quantize(&self, palette: &[Pixel; 256]) -> IndexedTexture {
+        let mut indexed = IndexedTexture::new(self.w, self.h, *palette);
+        for (i, &p) in indexed.indices.iter_mut().zip(self.pixels.iter()) {
+            *i = nearest_palette_index(palette, p);
+        }
+        indexed
+    }
+
+    // Quantizes after offsetting each pixel by a 4x4 Bayer threshold, so
+    // colors between palette entries become a pattern of the nearest ones.
+    // The offset range is the estimated spacing of the palette's colors.
+    pub fn dither_ordered(&self, palette: &[Pixel; 256]) -> IndexedTexture {
+        let mut distinct = palette.to_vec();
+        distinct.sort();
+        distinct.dedup();
+        let levels = (distinct.len() as f64).cbrt();
+        let spread = 255. / (levels - 1.).max(1.);
+
+        let mut indexed = IndexedTexture::new(self.w, self.h, *palette);
+        for y in 0 .. self.h as usize {
+            for x in 0 .. self.w as usize {
+                let i = y * self.w as usize + x;
+                let threshold =
+                    (BAYER_4X4[y % 4][x % 4] as f64 + 0.5) / 16. - 0.5;
+                let offset = |c: u8| {
+                    clamp(c as f64 + threshold * spread, 0., 255.) as u8
+                };
+                let (r, g, b) = self.pixels[i];
+                indexed.indices[i] = nearest_palette_index(
+                    palette,
+                    (offset(r), offset(g), offset(b))
+                );
+            }
+        }
+        indexed
+    }
+
+    // Converts linear RGB values to sRGB (gamma 2.2) for display.
+    pub fn to_srgb(&self) -> Texture {
+        let encode = |v: u8| {
+            ((v as f64 / 255.).powf(1. / 2.2) * 255.).round() as u8
+        };
+        let mut out = self.clone();
+        out.hdr = None;
+        for p in out.pixels.iter_mut() {
+            let (r, g, b) = *p;
+            *p = (encode(r), encode(g), encode(b));
+        }
+        out
+    }
+
+    // The width and height as little-endian u32s, then the pixels row by row
+    // as RGB bytes. Depths are not stored.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.pixels.len() * 3);
+        for &n in &[self.w, self.h] {
+            for i in 0 .. 4 { bytes.push((n >> (8 * i)) as u8) }
+        }
+        for &(r, g, b) in &self.pixels {
+            bytes.extend_from_slice(&[r, g, b]);
+        }
+        bytes
+    }
+
+    // The inverse of `to_bytes`. Fails on truncated or trailing data and on
+    // sizes over `MAX_TEXTURE_PIXELS`.
+    pub fn from_bytes(data: &[u8]) -> Result<Texture, &'static str> {
+        if data.len() < 8 { return Err("texture data is missing its header") }
+        let read_u32 = |at: usize| {
+            data[at .. at + 4].iter().rev()
+                .fold(0, |n, &byte| n << 8 | byte as Dimension)
+        };
+        let (w, h) = (read_u32(0), read_u32(4));
+        let len = w as u64 * h as u64;
+        if len > MAX_TEXTURE_PIXELS as u64 {
+            return Err("texture is too large");
+        }
+
+        // Check the payload before allocating anything for the header's size.
+        let data = &data[8 ..];
+        if data.len() as u64 != len * 3 {
+            return Err("texture data does not match its size");
+        }
+        let mut texture = Texture::new(w, h);
+        for (p, px) in texture.pixels.iter_mut().zip(data.chunks(3)) {
+            *p = (px[0], px[1], px[2]);
+        }
+        Ok(texture)
+    }
+
+    // A `w` by `h` texture from row-major RGB data, 3 bytes per pixel. Fails
+    // unless `data` holds exactly that many bytes, and on sizes over
+    // `MAX_TEXTURE_PIXELS`.
+    pub fn from_rgb_slice(data: &[u8], w: Dimension, h: Dimension)
+        -> Result<Texture, Box<error::Error>>
+    {
+        Texture::from_channels(data, w, h, 3)
+    }
+
+    // Like `from_rgb_slice`, with 4 bytes per pixel. Alpha is dropped.
+    pub fn from_rgba_slice(data: &[u8], w: Dimension, h: Dimension)
+        -> Result<Texture, Box<error::Error>>
+    {
+        Texture::from_channels(data, w, h, 4)
+    }
+
+    // Reads the first three of every `channels` bytes as a pixel.
+    fn from_channels(
+        data: &[u8],
+        w: Dimension,
+        h: Dimension,
+        channels: usize
+    )
+        -> Result<Texture, Box<error::Error>>
+    {
+        let mut texture = match Texture::try_new(w, h) {
+            Some(texture) => texture,
+            None => return Err(From::from(format!(
+                "{}x{} texture is too large", w, h
+            ))),
+        };
+
+        let expected = texture.pixels.len() * channels;
+        if data.len() != expected {
+            return Err(From::from(format!(
+                "{}x{} texture needs {} bytes of pixels, got {}",
+                w, h, expected, data.len()
+            )));
         }
+        for (p, px) in texture.pixels.iter_mut().zip(data.chunks(channels)) {
+            *p = (px[0], px[1], px[2]);
+        }
+        Ok(texture)
     }
 }
 
-impl Display for Texture {
+impl<D: DepthValue> Display for Texture<D> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // Draw top bar.
         try!(write!(f, "{:-^1$}\n", "", self.w as usize * 2 + 3));
 
         // Draw rows.
-        self.pixels
-            .chunks(self.w as usize)
-            .map(|row| {
-                try!(write!(f, "| "));
-                for p in row {
-                    try!(write!(f, "{} ", pixel::as_char(*p)));
-                }
-                try!(write!(f, "|\n"));
-                Ok(())
-            }).collect::<Result<Vec<_>, fmt::Error>>()?;
+        for row in self.rows() {
+            try!(write!(f, "| "));
+            for p in row {
+                try!(write!(f, "{} ", pixel::as_char(*p)));
+            }
+            try!(write!(f, "|\n"));
+        }
 
         // Draw bottom bar.
         try!(write!(f, "{:-^1$}\n", "", self.w as usize * 2 + 3));
         Ok(())
     }
 }
+
+
+// A framebuffer of linear floating-point colors. Values are not clamped, so
+// channels may exceed 1.0 until the buffer is tone mapped for display.
+#[derive(Clone)]
+pub struct TextureHdr {
+    pub w: Dimension,
+    pub h: Dimension,
+    pub pixels: Vec<HdrPixel>,
+}
+
+impl TextureHdr {
+    pub fn new(w: Dimension, h: Dimension) -> TextureHdr {
+        TextureHdr {
+            w: w,
+            h: h,
+            pixels: vec![(0., 0., 0.); w as usize * h as usize],
+        }
+    }
+
+    pub fn from_texture<D: DepthValue>(texture: &Texture<D>) -> TextureHdr {
+        TextureHdr::from_pixels(texture.w, texture.h, &texture.pixels)
+    }
+
+    fn from_pixels(w: Dimension, h: Dimension, pixels: &[Pixel])
+        -> TextureHdr
+    {
+        TextureHdr {
+            w: w,
+            h: h,
+            pixels: pixels.iter().map(|&p| to_hdr(p)).collect(),
+        }
+    }
+
+    pub fn set_pixel(
+        &mut self,
+        x: PixCoord,
+        y: PixCoord,
+        color: HdrPixel
+    ) {
+        if x < 0 || y < 0 { return }
+        if x as Dimension >= self.w || y as Dimension >= self.h { return }
+        self.pixels[y as usize * self.w as usize + x as usize] = color;
+    }
+
+    // Adds `other` onto this buffer channel by channel.
+    pub fn add(&mut self, other: &TextureHdr) {
+        assert!(self.w == other.w && self.h == other.h);
+        for (p, &(r, g, b)) in self.pixels.iter_mut().zip(other.pixels.iter()) {
+            p.0 += r;
+            p.1 += g;
+            p.2 += b;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for p in self.pixels.iter_mut() {
+            *p = (0., 0., 0.);
+        }
+    }
+
+    // Compresses each channel into [0, 1] with extended Reinhard,
+    // c (1 + c / w^2) / (1 + c), where the white point w is the brightest
+    // channel in the buffer. Buffers with nothing brighter than 1.0 come out
+    // unchanged.
+    pub fn tonemap_reinhard(&self) -> Texture {
+        let white = self.pixels.iter()
+            .map(|&(r, g, b)| r.max(g).max(b))
+            .fold(1f32, f32::max);
+        let map = |c: f32| {
+            let c = c.max(0.);
+            let mapped = c * (1. + c / (white * white)) / (1. + c);
+            (clamp(mapped, 0., 1.) * 255.).round() as u8
+        };
+        let mut texture = Texture::new(self.w, self.h);
+        let pixels = texture.pixels.iter_mut().zip(self.pixels.iter());
+        for (p, &(r, g, b)) in pixels {
+            *p = (map(r), map(g), map(b));
+        }
+        texture
+    }
+}
+
+
+// A texture storing one byte per pixel that indexes into a 256-color palette.
+#[derive(Clone)]
+pub struct IndexedTexture {
+    pub palette: [Pixel; 256],
+    pub indices: Vec<u8>,
+    pub w: Dimension,
+    pub h: Dimension,
+}
+
+impl IndexedTexture {
+    pub fn new(w: Dimension, h: Dimension, palette: [Pixel; 256])
+        -> IndexedTexture
+    {
+        IndexedTexture {
+            palette: palette,
+            indices: vec![0; w as usize * h as usize],
+            w: w,
+            h: h,
+        }
+    }
+
+    pub fn to_texture(&self) -> Texture {
+        let mut texture = Texture::new(self.w, self.h);
+        for (p, &i) in texture.pixels.iter_mut().zip(self.indices.iter()) {
+            *p = self.palette[i as usize];
+        }
+        texture
+    }
+
+    pub fn nearest_palette_index(&self, color: Pixel) -> u8 {
+        nearest_palette_index(&self.palette, color)
+    }
+}
+
+// Index of the palette entry closest to `color` in RGB space.
+fn nearest_palette_index(palette: &[Pixel; 256], color: Pixel) -> u8 {
+    let dist = |p: Pixel| {
+        let dr = p.0 as i32 - color.0 as i32;
+        let dg = p.1 as i32 - color.1 as i32;
+        let db = p.2 as i32 - color.2 as i32;
+        dr * dr + dg * dg + db * db
+    };
+
+    let mut best = 0;
+    for i in 1 .. palette.len() {
+        if dist(palette[i]) < dist(palette[best]) { best = i }
+    }
+    best as u8
+}
+
+
+// A rectangular window onto part of a texture.
+#[derive(Clone, Copy)]
+pub struct TextureView<'a> {
+    pub texture: &'a Texture,
+    pub x: PixCoord,
+    pub y: PixCoord,
+    pub w: Dimension,
+    pub h: Dimension,
+}
+
+impl<'a> TextureView<'a> {
+    pub fn whole(texture: &'a Texture) -> TextureView<'a> {
+        TextureView {
+            texture: texture,
+            x: 0,
+            y: 0,
+            w: texture.w,
+            h: texture.h,
+        }
+    }
+
+    // Pixel at (x, y) relative to the view's top-left corner.
+    pub fn pixel(&self, x: Dimension, y: Dimension) -> Pixel {
+        let tx = self.x as usize + x as usize;
+        let ty = self.y as usize + y as usize;
+        self.texture.pixels[ty * self.texture.w as usize + tx]
+    }
+}
+
+
+// A texture holding several images, each addressed by name.
+pub struct TextureAtlas {
+    pub texture: Texture,
+    pub regions: HashMap<String, (PixCoord, PixCoord, Dimension, Dimension)>,
+}
+
+impl TextureAtlas {
+    pub fn new(texture: Texture) -> TextureAtlas {
+        TextureAtlas {
+            texture: texture,
+            regions: HashMap::new(),
+        }
+    }
+
+    // Slices a sprite sheet into equal tiles named "0", "1", ... in row-major
+    // order. Partial tiles at the right and bottom edges are dropped.
+    pub fn from_grid(texture: Texture, tile_w: Dimension, tile_h: Dimension)
+        -> TextureAtlas
+    {
+        let mut atlas = TextureAtlas::new(texture);
+        if tile_w == 0 || tile_h == 0 { return atlas }
+
+        let cols = atlas.texture.w / tile_w;
+        let rows = atlas.texture.h / tile_h;
+        for row in 0 .. rows {
+            for col in 0 .. cols {
+                atlas.add_region(
+                    &(row * cols + col).to_string(),
+                    (col * tile_w) as PixCoord,
+                    (row * tile_h) as PixCoord,
+                    tile_w,
+                    tile_h
+                );
+            }
+        }
+        atlas
+    }
+
+    pub fn add_region(
+        &mut self,
+        name: &str,
+        x: PixCoord,
+        y: PixCoord,
+        w: Dimension,
+        h: Dimension
+    ) {
+        self.regions.insert(name.to_string(), (x, y, w, h));
+    }
+
+    pub fn get_region(&self, name: &str) -> Option<TextureView<'_>> {
+        self.regions.get(name).map(|&(x, y, w, h)| TextureView {
+            texture: &self.texture,
+            x: x,
+            y: y,
+            w: w,
+            h: h,
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_region_left_of_texture() {
+        let mut t = Texture::new(4, 4);
+        t.fill_region(-1000, 1, 7, 2, (255, 0, 0));
+        assert!(t.pixels.iter().all(|&p| p == (0, 0, 0)));
+    }
+
+    #[test]
+    fn to_srgb_mid_gray() {
+        let mut t = Texture::new(1, 1);
+        t.set_all_pixels((128, 128, 128));
+        let (r, g, b) = t.to_srgb().pixels[0];
+
+        let expected = (128. / 255f64).powf(1. / 2.2) * 255.;
+        assert!((r as f64 - expected).abs() <= 0.5);
+        assert_eq!((r, g, b), (186, 186, 186));
+    }
+
+    #[test]
+    fn to_srgb_keeps_black_and_white() {
+        let mut t = Texture::new(2, 1);
+        t.pixels[1] = pixel::WHITE;
+        assert_eq!(t.to_srgb().pixels, vec![pixel::BLACK, pixel::WHITE]);
+    }
+
+    #[test]
+    fn hdr_keeps_overbright_values() {
+        let mut t = Texture::new(2, 1);
+        t.set_hdr(true);
+        t.set_blend_mode(BlendMode::Additive);
+        t.set_pixel(0, 0, 1., pixel::WHITE);
+        t.set_depth_test(DepthTest::Always);
+        t.set_pixel(0, 0, 1., pixel::WHITE);
+        t.set_pixel(1, 0, 1., pixel::WHITE);
+
+        assert_eq!(t.pixels[0], pixel::WHITE);
+        assert_eq!(t.hdr().unwrap().pixels, vec![(2., 2., 2.), (1., 1., 1.)]);
+
+        // The white point is 2.0, so 1.0 maps to 1 (1 + 1/4) / 2.
+        let mapped = t.tonemapped();
+        assert_eq!(mapped.pixels, vec![pixel::WHITE, (159, 159, 159)]);
+        assert_eq!(mapped.read_depth(0, 0), Some(1.));
+    }
+
+    #[test]
+    fn tonemap_leaves_unit_range_alone() {
+        let mut hdr = TextureHdr::new(3, 1);
+        hdr.pixels = vec![(0., 0., 0.), (0.5, 0.25, 0.), (1., 1., 1.)];
+        let mapped = hdr.tonemap_reinhard();
+        assert_eq!(
+            mapped.pixels,
+            vec![pixel::BLACK, (128, 64, 0), pixel::WHITE]
+        );
+    }
+
+    // The cube of `levels` evenly spaced values per channel, repeated to
+    // fill the palette.
+    fn cube_palette(levels: &[u8]) -> [Pixel; 256] {
+        let mut colors = Vec::new();
+        for &r in levels {
+            for &g in levels {
+                for &b in levels { colors.push((r, g, b)) }
+            }
+        }
+        let mut palette = [pixel::BLACK; 256];
+        for (i, p) in palette.iter_mut().enumerate() {
+            *p = colors[i % colors.len()];
+        }
+        palette
+    }
+
+    fn dithered_colors(t: &Texture, palette: &[Pixel; 256]) -> Vec<Pixel> {
+        t.dither_ordered(palette).to_texture().pixels
+    }
+
+    #[test]
+    fn dither_ordered_mixes_the_nearest_entries() {
+        let palette = cube_palette(&[0, 255]);
+        let mut gradient = Texture::new(16, 4);
+        for (i, p) in gradient.pixels.iter_mut().enumerate() {
+            let v = (i % 16 * 17) as u8;
+            *p = (v, v, v);
+        }
+        let out = dithered_colors(&gradient, &palette);
+        assert!(out.iter().all(|&p| p == pixel::BLACK || p == pixel::WHITE));
+
+        let whites = |x: usize| {
+            (0 .. 4).filter(|&y| out[y * 16 + x] == pixel::WHITE).count()
+        };
+        assert_eq!(whites(0), 0);
+        assert_eq!(whites(15), 4);
+        assert!((1 .. 15).any(|x| whites(x) == 2));
+        // Each 4x4 tile is at least as light as the one left of it.
+        let tile = |i: usize| (4 * i .. 4 * i + 4).map(&whites).sum::<usize>();
+        for i in 1 .. 4 {
+            assert!(tile(i) >= tile(i - 1));
+        }
+
+        // Mid-gray covers half of each 4x4 tile.
+        let mut gray = Texture::new(4, 4);
+        gray.set_all_pixels((128, 128, 128));
+        let out = dithered_colors(&gray, &palette);
+        assert_eq!(out.iter().filter(|&&p| p == pixel::WHITE).count(), 8);
+    }
+
+    #[test]
+    fn dither_spread_follows_the_palette_spacing() {
+        // 27 distinct colors make three levels per channel, 127.5 apart, so
+        // 100 dithers between 0 and 128 and never reaches 255.
+        let palette = cube_palette(&[0, 128, 255]);
+        let mut t = Texture::new(4, 4);
+        t.set_all_pixels((100, 100, 100));
+        let out = dithered_colors(&t, &palette);
+        assert!(out.contains(&(0, 0, 0)));
+        assert!(out.contains(&(128, 128, 128)));
+        assert!(out.iter().all(|&p| p == (0, 0, 0) || p == (128, 128, 128)));
+    }
+
+    #[test]
+    fn checkerboard_corners_and_center() {
+        let (a, b) = (pixel::RED, pixel::BLUE);
+        let t = Texture::checkerboard(10, 10, 2, a, b);
+        assert_eq!(t.read_pixel(0, 0), Some(a));
+        assert_eq!(t.read_pixel(1, 1), Some(a));
+        assert_eq!(t.read_pixel(2, 0), Some(b));
+        assert_eq!(t.read_pixel(0, 2), Some(b));
+        // Pixel 9 is in cell 4 and pixel 5 in cell 2, both even.
+        assert_eq!(t.read_pixel(9, 0), Some(a));
+        assert_eq!(t.read_pixel(0, 9), Some(a));
+        assert_eq!(t.read_pixel(9, 9), Some(a));
+        assert_eq!(t.read_pixel(5, 5), Some(a));
+        assert_eq!(t.read_pixel(6, 5), Some(b));
+
+        // A zero cell size is treated as one pixel.
+        let t = Texture::checkerboard(3, 1, 0, a, b);
+        assert_eq!(t.pixels, vec![a, b, a]);
+    }
+
+    // The color left after drawing red at depth `first`, then blue at depth
+    // `second`, under `mode`.
+    fn after_two_draws(mode: DepthTest, first: Coord, second: Coord) -> Pixel {
+        let mut t = Texture::new(1, 1);
+        t.set_depth_test(DepthTest::Always);
+        t.set_pixel(0, 0, first, pixel::RED);
+        t.set_depth_test(mode);
+        t.set_pixel(0, 0, second, pixel::BLUE);
+        t.pixels[0]
+    }
+
+    #[test]
+    fn depth_test_modes() {
+        let (red, blue) = (pixel::RED, pixel::BLUE);
+        let cases = [
+            //                        nearer, farther, equal
+            (DepthTest::Less,         blue,   red,     red),
+            (DepthTest::LessEqual,    blue,   red,     blue),
+            (DepthTest::Greater,      red,    blue,    red),
+            (DepthTest::GreaterEqual, red,    blue,    blue),
+            (DepthTest::Equal,        red,    red,     blue),
+            (DepthTest::Always,       blue,   blue,    blue),
+            (DepthTest::Never,        red,    red,     red),
+        ];
+        for &(mode, nearer, farther, equal) in &cases {
+            assert_eq!(after_two_draws(mode, 2., 1.), nearer, "{:?}", mode);
+            assert_eq!(after_two_draws(mode, 1., 2.), farther, "{:?}", mode);
+            assert_eq!(after_two_draws(mode, 1., 1.), equal, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn failed_depth_tests_keep_the_depth() {
+        let mut t = Texture::new(1, 1);
+        t.set_pixel(0, 0, 1., pixel::RED);
+        t.set_pixel(0, 0, 2., pixel::BLUE);
+        assert_eq!(t.read_depth(0, 0), Some(1.));
+        t.set_depth_test(DepthTest::Greater);
+        t.set_pixel(0, 0, 2., pixel::BLUE);
+        assert_eq!(t.read_depth(0, 0), Some(2.));
+        assert!(!t.depth_passes(0, 0, 2.));
+        assert!(t.depth_passes(0, 0, 3.));
+    }
+
+    #[test]
+    fn clear_depth_keeps_colors() {
+        let mut t = Texture::new(2, 1);
+        t.set_pixel(0, 0, 1., pixel::RED);
+        t.set_pixel(1, 0, 5., pixel::GREEN);
+        t.clear_depth();
+        assert_eq!(t.pixels, vec![pixel::RED, pixel::GREEN]);
+        assert_eq!(t.read_depth(0, 0), Some(f64::INFINITY));
+
+        // Anything may be drawn over the old colors again.
+        t.set_pixel(1, 0, 9., pixel::BLUE);
+        assert_eq!(t.pixels, vec![pixel::RED, pixel::BLUE]);
+    }
+
+    #[test]
+    fn compare_and_diff() {
+        let mut a = Texture::new(3, 2);
+        a.set_all_pixels((100, 100, 100));
+        let mut b = a.clone();
+        b.set_pixel(1, 0, 0., (104, 98, 100));
+        b.set_pixel(2, 1, 0., (100, 100, 90));
+
+        assert!(a.compare(&a, 0));
+        assert!(!a.compare(&b, 9));
+        assert!(a.compare(&b, 10));
+        assert!(!a.compare(&Texture::new(2, 3), 255));
+
+        // The largest difference, 10, is stretched to white.
+        let d = a.diff(&b).unwrap();
+        assert_eq!(d.read_pixel(0, 0), Some(pixel::BLACK));
+        assert_eq!(d.read_pixel(1, 0), Some((102, 51, 0)));
+        assert_eq!(d.read_pixel(2, 1), Some((0, 0, 255)));
+        assert!(a.diff(&a).unwrap().pixels.iter().all(|&p| p == pixel::BLACK));
+        assert!(a.diff(&Texture::new(2, 3)).is_none());
+    }
+
+    #[test]
+    fn flips_mirror_an_off_center_pixel() {
+        let mut t = Texture::new(5, 3);
+        t.set_pixel(1, 0, 2., pixel::RED);
+
+        let h = t.horizontal_flip();
+        assert_eq!(h.read_pixel(3, 0), Some(pixel::RED));
+        assert_eq!(h.read_depth(3, 0), Some(2.));
+        assert_eq!(h.read_pixel(1, 0), Some(pixel::BLACK));
+
+        let v = t.vertical_flip();
+        assert_eq!(v.read_pixel(1, 2), Some(pixel::RED));
+        assert_eq!(v.read_depth(1, 2), Some(2.));
+        assert_eq!(v.read_pixel(1, 0), Some(pixel::BLACK));
+
+        // The in-place flips agree, and flipping twice undoes them.
+        let mut m = t.clone();
+        m.flip_h_mut();
+        m.flip_v_mut();
+        assert_eq!(m.read_pixel(3, 2), Some(pixel::RED));
+        assert_eq!(m.pixels.iter().filter(|&&p| p != pixel::BLACK).count(), 1);
+        m.flip_v_mut();
+        m.flip_h_mut();
+        assert_eq!(m.pixels, t.pixels);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut t = Texture::new(3, 2);
+        t.set_pixel(0, 0, 0., (1, 2, 3));
+        t.set_pixel(2, 1, 0., (250, 128, 7));
+        let back = Texture::from_bytes(&t.to_bytes()).unwrap();
+        assert_eq!((back.w, back.h), (3, 2));
+        assert_eq!(back.pixels, t.pixels);
+
+        let empty = Texture::from_bytes(&Texture::new(0, 5).to_bytes());
+        assert_eq!(empty.map(|t| (t.w, t.h)), Ok((0, 5)));
+    }
+
+    #[test]
+    fn bytes_layout() {
+        let mut t = Texture::new(258, 1);
+        t.set_pixel(0, 0, 0., (10, 20, 30));
+        t.set_pixel(257, 0, 0., (40, 50, 60));
+        let bytes = t.to_bytes();
+        assert_eq!(bytes.len(), 8 + 258 * 3);
+        // Little-endian width then height.
+        assert_eq!(&bytes[.. 8], &[2, 1, 0, 0, 1, 0, 0, 0]);
+        assert_eq!(&bytes[8 .. 11], &[10, 20, 30]);
+        assert_eq!(&bytes[11 .. 14], &[0, 0, 0]);
+        assert_eq!(&bytes[8 + 257 * 3 ..], &[40, 50, 60]);
+    }
+
+    #[test]
+    fn from_bytes_rejects_bad_data() {
+        let bytes = Texture::new(2, 2).to_bytes();
+        assert!(Texture::from_bytes(&bytes[.. 5]).is_err());
+        assert!(Texture::from_bytes(&bytes[.. bytes.len() - 1]).is_err());
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(Texture::from_bytes(&long).is_err());
+        let huge = [255, 255, 255, 255, 255, 255, 255, 255];
+        assert_eq!(
+            Texture::from_bytes(&huge).map(|_| ()),
+            Err("texture is too large")
+        );
+        // 8192 x 8192 is allowed, but there's no data for it.
+        let unbacked = [0, 32, 0, 0, 0, 32, 0, 0];
+        assert_eq!(
+            Texture::from_bytes(&unbacked).map(|_| ()),
+            Err("texture data does not match its size")
+        );
+    }
+
+    #[test]
+    fn box_blur_spreads_a_bright_pixel() {
+        let mut t = Texture::new(7, 7);
+        t.set_pixel(3, 3, 0., (225, 90, 0));
+        let out = t.box_blur(1);
+        for y in 0 .. 7 {
+            for x in 0 .. 7 {
+                let near = (x - 3i16).abs() <= 1 && (y - 3i16).abs() <= 1;
+                let expected = if near { (25, 10, 0) } else { pixel::BLACK };
+                assert_eq!(out.read_pixel(x, y), Some(expected));
+            }
+        }
+
+        // Clamped edges count the corner pixel four times.
+        let mut t = Texture::new(5, 5);
+        t.set_pixel(0, 0, 0., (225, 225, 225));
+        assert_eq!(t.box_blur(1).read_pixel(0, 0), Some((100, 100, 100)));
+        assert_eq!(t.box_blur(0).pixels, t.pixels);
+    }
+
+    #[test]
+    fn gaussian_blur_spreads_a_bright_pixel() {
+        let mut t = Texture::new(11, 11);
+        t.set_pixel(5, 5, 0., pixel::WHITE);
+        let out = t.gaussian_blur(1.);
+        let gray = |x: PixCoord, y: PixCoord| out.read_pixel(x, y).unwrap().0;
+
+        // Highest at the center, falling off evenly in every direction.
+        assert!(gray(5, 5) < 255 && gray(5, 5) > gray(6, 5));
+        assert!(gray(6, 5) > gray(7, 5) && gray(7, 5) > 0);
+        assert_eq!(gray(4, 5), gray(6, 5));
+        assert_eq!(gray(5, 4), gray(5, 6));
+        assert_eq!(gray(4, 4), gray(6, 6));
+        // Nothing past the three-sigma cutoff.
+        assert_eq!(gray(1, 5), 0);
+        assert_eq!(gray(5, 9), 0);
+
+        let total: u32 = out.pixels.iter().map(|p| p.0 as u32).sum();
+        assert!(total > 240 && total < 270, "{}", total);
+    }
+
+    #[test]
+    fn from_rgb_slice_reads_rows_in_order() {
+        let data = [
+            1, 2, 3,  4, 5, 6,
+            7, 8, 9,  10, 11, 12,
+            13, 14, 15,  16, 17, 18,
+        ];
+        let t = Texture::from_rgb_slice(&data, 2, 3).unwrap();
+        assert_eq!(t.read_pixel(0, 0), Some((1, 2, 3)));
+        assert_eq!(t.read_pixel(1, 0), Some((4, 5, 6)));
+        assert_eq!(t.read_pixel(0, 1), Some((7, 8, 9)));
+        assert_eq!(t.read_pixel(1, 2), Some((16, 17, 18)));
+
+        assert!(Texture::from_rgb_slice(&data, 3, 2).is_ok());
+        assert!(Texture::from_rgb_slice(&data, 2, 2).is_err());
+        assert!(Texture::from_rgb_slice(&data[.. 17], 2, 3).is_err());
+        assert!(Texture::from_rgb_slice(&[], 0, 0).is_ok());
+    }
+
+    #[test]
+    fn from_rgba_slice_drops_alpha() {
+        let data = [
+            1, 2, 3, 255,  4, 5, 6, 0,
+            7, 8, 9, 128,  10, 11, 12, 1,
+        ];
+        let t = Texture::from_rgba_slice(&data, 2, 2).unwrap();
+        assert_eq!(
+            t.pixels,
+            vec![(1, 2, 3), (4, 5, 6), (7, 8, 9), (10, 11, 12)]
+        );
+        assert!(Texture::from_rgba_slice(&data[.. 12], 2, 2).is_err());
+        // Too many pixels fail before the data is looked at.
+        assert!(Texture::from_rgba_slice(&data, 1 << 20, 1 << 20).is_err());
+    }
+}
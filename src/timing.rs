@@ -0,0 +1,116 @@
+use std::collections::VecDeque;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+
+const NANOS_PER_SECOND: u32 = 1_000_000_000;
+const FRAME_WINDOW: usize = 60;
+
+// Paces a loop to a target frame rate and keeps statistics over the most
+// recent frames.
+pub struct FrameLimiter {
+    pub target_fps: u32,
+    pub actual_fps: f64,
+    frame_times: VecDeque<Duration>,
+    frame_start: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(target_fps: u32) -> FrameLimiter {
+        FrameLimiter {
+            target_fps: target_fps,
+            actual_fps: 0.,
+            frame_times: VecDeque::with_capacity(FRAME_WINDOW),
+            frame_start: Instant::now(),
+        }
+    }
+
+    pub fn target_frame_len(&self) -> Duration {
+        Duration::new(0, NANOS_PER_SECOND / self.target_fps.max(1))
+    }
+
+    // Call once at the end of each frame. Sleeps out whatever is left of the
+    // target frame length and returns how long the whole frame took.
+    pub fn tick(&mut self) -> Duration {
+        let work_len = self.frame_start.elapsed();
+        let target_frame_len = self.target_frame_len();
+        if work_len < target_frame_len {
+            thread::sleep(target_frame_len - work_len);
+        }
+
+        let frame_len = self.frame_start.elapsed();
+        self.frame_start = Instant::now();
+        self.record(frame_len);
+        frame_len
+    }
+
+    fn record(&mut self, frame_len: Duration) {
+        if self.frame_times.len() == FRAME_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_len);
+
+        let avg = duration_secs(self.avg_frame_time());
+        self.actual_fps = if avg > 0. { 1. / avg } else { 0. };
+    }
+
+    pub fn min_frame_time(&self) -> Duration {
+        self.frame_times.iter().cloned().min().unwrap_or_default()
+    }
+
+    pub fn max_frame_time(&self) -> Duration {
+        self.frame_times.iter().cloned().max().unwrap_or_default()
+    }
+
+    pub fn avg_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() { return Duration::new(0, 0) }
+        let total: Duration = self.frame_times.iter().sum();
+        total / self.frame_times.len() as u32
+    }
+}
+
+pub fn duration_secs(d: Duration) -> f64 {
+    d.as_secs() as f64 + d.subsec_nanos() as f64 / NANOS_PER_SECOND as f64
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(n: u64) -> Duration { Duration::from_millis(n) }
+
+    #[test]
+    fn statistics_of_recorded_frames() {
+        let mut limiter = FrameLimiter::new(60);
+        assert_eq!(limiter.avg_frame_time(), ms(0));
+        assert_eq!(limiter.min_frame_time(), ms(0));
+        assert_eq!(limiter.actual_fps, 0.);
+
+        for &n in &[20, 10, 30] { limiter.record(ms(n)) }
+        assert_eq!(limiter.min_frame_time(), ms(10));
+        assert_eq!(limiter.max_frame_time(), ms(30));
+        assert_eq!(limiter.avg_frame_time(), ms(20));
+        assert!((limiter.actual_fps - 50.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn statistics_cover_the_last_frames_only() {
+        let mut limiter = FrameLimiter::new(60);
+        limiter.record(ms(1000));
+        for _ in 0 .. FRAME_WINDOW { limiter.record(ms(4)) }
+        assert_eq!(limiter.max_frame_time(), ms(4));
+        assert_eq!(limiter.avg_frame_time(), ms(4));
+        assert!((limiter.actual_fps - 250.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tick_waits_out_the_frame() {
+        let mut limiter = FrameLimiter::new(200);
+        assert_eq!(limiter.target_frame_len(), ms(5));
+        assert!(limiter.tick() >= ms(5));
+        assert_eq!(FrameLimiter::new(0).target_frame_len(), ms(1000));
+        assert_eq!(duration_secs(Duration::new(2, 500_000_000)), 2.5);
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use std::ops;
 
 pub type Coord = f64;
@@ -39,6 +40,39 @@ impl Triangle {
         let d2 = self.p3 - self.p1;
         d1.cross(d2).normalized()
     }
+
+    pub fn area(self) -> f64 {
+        let d1 = self.p2 - self.p1;
+        let d2 = self.p3 - self.p1;
+        d1.cross(d2).magnitude() / 2.
+    }
+
+    pub fn perimeter(self) -> f64 {
+        (self.p2 - self.p1).magnitude()
+            + (self.p3 - self.p2).magnitude()
+            + (self.p1 - self.p3).magnitude()
+    }
+
+    // Splits the triangle at its edge midpoints into three corner triangles
+    // and the middle one, all with the original winding.
+    pub fn subdivide(&self) -> [Triangle; 4] {
+        let m12 = (self.p1 + self.p2) * 0.5;
+        let m23 = (self.p2 + self.p3) * 0.5;
+        let m31 = (self.p3 + self.p1) * 0.5;
+        [
+            trigon![self.p1, m12, m31],
+            trigon![m12, self.p2, m23],
+            trigon![m31, m23, self.p3],
+            trigon![m12, m23, m31],
+        ]
+    }
+
+    // Whether the triangle has (nearly) zero area, in which case it has no
+    // normal and cannot be filled.
+    pub fn is_degenerate(self) -> bool {
+        const EPSILON: f64 = 1e-10;
+        self.area() < EPSILON
+    }
 }
 
 impl ops::Mul<Transform> for Triangle {
@@ -208,6 +242,15 @@ impl Transform {
         Transform { data: data }
     }
 
+    // Rows of the matrix, which is applied to column vectors.
+    pub fn from_array(data: [[f64; DIM + 1]; DIM + 1]) -> Transform {
+        Transform { data: data }
+    }
+
+    pub fn to_array(self) -> [[f64; DIM + 1]; DIM + 1] {
+        self.data
+    }
+
     pub fn translate(off: Point) -> Transform {
         let mut t = Transform::identity();
         let arr_in = off.to_array();
@@ -242,6 +285,27 @@ impl Transform {
         t
     }
 
+    // Rotation by `angle` radians counterclockwise about `axis` (looking
+    // down the axis towards the origin), by Rodrigues' formula. This matches
+    // rotate_y(angle) about the y axis, but rotate_x and rotate_z turn the
+    // other way: they equal rotate_axis_angle(axis, -angle).
+    pub fn rotate_axis_angle(axis: Point, angle: f64) -> Transform {
+        let k = axis.normalized();
+        let (s, c) = angle.sin_cos();
+        let v = 1. - c;
+        let mut t = Transform::identity();
+        t.data[0][0] = c + k.x * k.x * v;
+        t.data[0][1] = k.x * k.y * v - k.z * s;
+        t.data[0][2] = k.x * k.z * v + k.y * s;
+        t.data[1][0] = k.y * k.x * v + k.z * s;
+        t.data[1][1] = c + k.y * k.y * v;
+        t.data[1][2] = k.y * k.z * v - k.x * s;
+        t.data[2][0] = k.z * k.x * v - k.y * s;
+        t.data[2][1] = k.z * k.y * v + k.x * s;
+        t.data[2][2] = c + k.z * k.z * v;
+        t
+    }
+
     pub fn scale(x: f64, y: f64, z: f64) -> Transform {
         let mut data = [[0.; DIM + 1]; DIM + 1];
         data[0][0] = x;
@@ -251,6 +315,42 @@ impl Transform {
         Transform { data: data }
     }
 
+    pub fn scale_uniform(s: f64) -> Transform {
+        Transform::scale(s, s, s)
+    }
+
+    // Each factor is named source then target: `xy` adds xy * x to y, `zx`
+    // adds zx * z to x, and so on.
+    pub fn shear(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64)
+        -> Transform
+    {
+        let mut t = Transform::identity();
+        t.data[0][1] = yx;
+        t.data[0][2] = zx;
+        t.data[1][0] = xy;
+        t.data[1][2] = zy;
+        t.data[2][0] = xz;
+        t.data[2][1] = yz;
+        t
+    }
+
+    // Mirrors across the plane through the origin with normal `n`, which
+    // need not be unit length: I - 2 n n^T / (n . n). A zero normal gives
+    // the identity.
+    pub fn reflect(n: Point) -> Transform {
+        let mut t = Transform::identity();
+        let len2 = n.dot(n);
+        if len2 == 0. { return t }
+        let n = [n.x, n.y, n.z];
+        for (i, row) in t.data.iter_mut().take(DIM).enumerate() {
+            for (j, &nj) in n.iter().enumerate() {
+                row[j] -= 2. * n[i] * nj / len2;
+            }
+        }
+        t
+    }
+
+    // Divides x and y by -z. Depth is not kept: every z maps to 1.
     pub fn perspective() -> Transform {
         let mut t = Transform::identity();
         t.data[DIM    ][DIM    ] =  0.;
@@ -258,6 +358,166 @@ impl Transform {
         t.data[DIM - 1][DIM - 1] = -1.;
         t
     }
+
+    // OpenGL convention: right-handed, looking down -z, with `fov` the
+    // vertical field of view in radians. Depth maps to [-1, 1], so z = -near
+    // goes to -1 and z = -far to 1.
+    pub fn perspective_opengl(fov: f64, aspect: f64, near: f64, far: f64)
+        -> Transform
+    {
+        let f = 1. / (fov / 2.).tan();
+        let mut t = Transform::identity();
+        t.data[0][0] = f / aspect;
+        t.data[1][1] = f;
+        t.data[2][2] = (far + near) / (near - far);
+        t.data[2][3] = 2. * far * near / (near - far);
+        t.data[3][2] = -1.;
+        t.data[3][3] = 0.;
+        t
+    }
+
+    // Direct3D convention, right-handed like `perspective_opengl`, but depth
+    // maps to [0, 1]: z = -near goes to 0 and z = -far to 1.
+    pub fn perspective_d3d(fov: f64, aspect: f64, near: f64, far: f64)
+        -> Transform
+    {
+        let f = 1. / (fov / 2.).tan();
+        let mut t = Transform::identity();
+        t.data[0][0] = f / aspect;
+        t.data[1][1] = f;
+        t.data[2][2] = far / (near - far);
+        t.data[2][3] = far * near / (near - far);
+        t.data[3][2] = -1.;
+        t.data[3][3] = 0.;
+        t
+    }
+
+    // transforms[0] * transforms[1] * ..., so the last one is applied
+    // first. The identity for an empty slice.
+    pub fn compose(transforms: &[Transform]) -> Transform {
+        transforms.iter().fold(Transform::identity(), |acc, &t| acc * t)
+    }
+
+    // Like `compose`, but in reverse: the first transform is applied first.
+    pub fn compose_inv(transforms: &[Transform]) -> Transform {
+        transforms.iter().rev().fold(Transform::identity(), |acc, &t| acc * t)
+    }
+
+    // Composes scaling, then rotation, then translation.
+    pub fn from_parts(translation: Point, rotation: Quaternion, scale: Point)
+        -> Transform
+    {
+        let r = rotation.to_transform();
+        let s = [scale.x, scale.y, scale.z];
+        let off = [translation.x, translation.y, translation.z];
+        let mut t = Transform::identity();
+        for (i, &o) in off.iter().enumerate() {
+            for (j, &sj) in s.iter().enumerate() {
+                t.data[i][j] = r.data[i][j] * sj;
+            }
+            t.data[i][DIM] = o;
+        }
+        t
+    }
+
+    // Splits an affine transform without shear into (translation, rotation,
+    // scale), the inverse of `from_parts`. A reflection is folded into a
+    // negative x scale.
+    pub fn decompose(&self) -> (Point, Quaternion, Point) {
+        let d = &self.data;
+        let translation = pt![d[0][DIM], d[1][DIM], d[2][DIM]];
+
+        let column = |j: usize| pt![d[0][j], d[1][j], d[2][j]];
+        let mut scale = pt![
+            column(0).magnitude(),
+            column(1).magnitude(),
+            column(2).magnitude()
+        ];
+        if column(0).dot(column(1).cross(column(2))) < 0. {
+            scale.x = -scale.x;
+        }
+
+        let s = [scale.x, scale.y, scale.z];
+        let mut r = [[0.; DIM]; DIM];
+        for (i, row) in r.iter_mut().enumerate() {
+            for (j, &sj) in s.iter().enumerate() {
+                row[j] = if sj == 0. { 0. } else { d[i][j] / sj };
+            }
+        }
+
+        (translation, Quaternion::from_rotation_matrix(r), scale)
+    }
+
+    // The inverse matrix, or None if the transform is singular (such as
+    // `perspective`, which flattens depth).
+    pub fn inverse(&self) -> Option<Transform> {
+        const N: usize = DIM + 1;
+        let mut a = self.data;
+        let mut inv = Transform::identity().data;
+
+        // Gauss-Jordan elimination with partial pivoting.
+        for col in 0 .. N {
+            let pivot = (col .. N)
+                .max_by(|&i, &j| {
+                    a[i][col].abs().partial_cmp(&a[j][col].abs())
+                        .unwrap_or(Ordering::Equal)
+                })
+                .unwrap_or(col);
+            if a[pivot][col].abs() < 1e-12 { return None }
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let p = a[col][col];
+            for j in 0 .. N {
+                a[col][j] /= p;
+                inv[col][j] /= p;
+            }
+            for i in 0 .. N {
+                if i == col { continue }
+                let f = a[i][col];
+                if f == 0. { continue }
+                for j in 0 .. N {
+                    a[i][j] -= f * a[col][j];
+                    inv[i][j] -= f * inv[col][j];
+                }
+            }
+        }
+
+        Some(Transform { data: inv })
+    }
+
+    // The transpose of the inverse of the upper-left 3x3 block, which carries
+    // normals so they stay perpendicular to surfaces under non-uniform scale.
+    // Normals still need renormalizing afterwards. A singular block yields
+    // its cofactor matrix, which points normals the same way up to length.
+    pub fn normal_matrix(&self) -> Transform {
+        let d = &self.data;
+        let row = |i: usize| pt![d[i][0], d[i][1], d[i][2]];
+        let cofactors = [
+            row(1).cross(row(2)),
+            row(2).cross(row(0)),
+            row(0).cross(row(1)),
+        ];
+        let det = row(0).dot(cofactors[0]);
+        let inv_det = if det == 0. { 1. } else { 1. / det };
+
+        let mut t = Transform::identity();
+        for (i, c) in cofactors.iter().enumerate() {
+            t.data[i][0] = c.x * inv_det;
+            t.data[i][1] = c.y * inv_det;
+            t.data[i][2] = c.z * inv_det;
+        }
+        t
+    }
+}
+
+// Applies the transform to a point; the same as `p * t`.
+impl ops::Mul<Point> for Transform {
+    type Output = Point;
+
+    fn mul(self, rhs: Point) -> Point {
+        rhs * self
+    }
 }
 
 impl ops::Mul for Transform {
@@ -277,3 +537,455 @@ impl ops::Mul for Transform {
         Transform { data: data }
     }
 }
+
+
+// A rotation as a unit quaternion w + xi + yj + zk.
+#[derive(Debug, Clone, Copy)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Quaternion {
+        Quaternion { w: w, x: x, y: y, z: z }
+    }
+
+    pub fn identity() -> Quaternion {
+        Quaternion::new(1., 0., 0., 0.)
+    }
+
+    // Rotation by `angle` radians counterclockwise about `axis` (looking
+    // down the axis towards the origin).
+    pub fn from_axis_angle(axis: Point, angle: f64) -> Quaternion {
+        let axis = axis.normalized();
+        let s = (angle / 2.).sin();
+        Quaternion::new((angle / 2.).cos(), axis.x * s, axis.y * s, axis.z * s)
+    }
+
+    // `r` must be a pure rotation, with rows applied to column vectors.
+    pub fn from_rotation_matrix(r: [[f64; DIM]; DIM]) -> Quaternion {
+        let trace = r[0][0] + r[1][1] + r[2][2];
+        let q = if trace > 0. {
+            let s = (trace + 1.).sqrt() * 2.;
+            Quaternion::new(
+                s / 4.,
+                (r[2][1] - r[1][2]) / s,
+                (r[0][2] - r[2][0]) / s,
+                (r[1][0] - r[0][1]) / s
+            )
+        } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+            let s = (1. + r[0][0] - r[1][1] - r[2][2]).sqrt() * 2.;
+            Quaternion::new(
+                (r[2][1] - r[1][2]) / s,
+                s / 4.,
+                (r[0][1] + r[1][0]) / s,
+                (r[0][2] + r[2][0]) / s
+            )
+        } else if r[1][1] > r[2][2] {
+            let s = (1. + r[1][1] - r[0][0] - r[2][2]).sqrt() * 2.;
+            Quaternion::new(
+                (r[0][2] - r[2][0]) / s,
+                (r[0][1] + r[1][0]) / s,
+                s / 4.,
+                (r[1][2] + r[2][1]) / s
+            )
+        } else {
+            let s = (1. + r[2][2] - r[0][0] - r[1][1]).sqrt() * 2.;
+            Quaternion::new(
+                (r[1][0] - r[0][1]) / s,
+                (r[0][2] + r[2][0]) / s,
+                (r[1][2] + r[2][1]) / s,
+                s / 4.
+            )
+        };
+        q.normalized()
+    }
+
+    pub fn to_transform(self) -> Transform {
+        let Quaternion { w, x, y, z } = self.normalized();
+        let mut t = Transform::identity();
+        t.data[0][0] = 1. - 2. * (y * y + z * z);
+        t.data[0][1] = 2. * (x * y - w * z);
+        t.data[0][2] = 2. * (x * z + w * y);
+        t.data[1][0] = 2. * (x * y + w * z);
+        t.data[1][1] = 1. - 2. * (x * x + z * z);
+        t.data[1][2] = 2. * (y * z - w * x);
+        t.data[2][0] = 2. * (x * z - w * y);
+        t.data[2][1] = 2. * (y * z + w * x);
+        t.data[2][2] = 1. - 2. * (x * x + y * y);
+        t
+    }
+
+    pub fn dot(self, other: Quaternion) -> f64 {
+        self.w * other.w +
+        self.x * other.x +
+        self.y * other.y +
+        self.z * other.z
+    }
+
+    pub fn magnitude(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalized(self) -> Quaternion {
+        let m = self.magnitude();
+        Quaternion::new(self.w / m, self.x / m, self.y / m, self.z / m)
+    }
+
+    pub fn conjugate(self) -> Quaternion {
+        Quaternion::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    // Spherical linear interpolation along the shorter arc, from `self` at
+    // t = 0 to `other` at t = 1.
+    pub fn slerp(self, other: Quaternion, t: f64) -> Quaternion {
+        let mut other = other;
+        let mut cos_theta = self.dot(other);
+        if cos_theta < 0. {
+            other = Quaternion::new(-other.w, -other.x, -other.y, -other.z);
+            cos_theta = -cos_theta;
+        }
+
+        // Nearly parallel: fall back to a normalized lerp.
+        let (a, b) = if cos_theta > 0.9995 {
+            (1. - t, t)
+        } else {
+            let theta = cos_theta.acos();
+            let sin_theta = theta.sin();
+            (
+                ((1. - t) * theta).sin() / sin_theta,
+                (t * theta).sin() / sin_theta
+            )
+        };
+        Quaternion::new(
+            a * self.w + b * other.w,
+            a * self.x + b * other.x,
+            a * self.y + b * other.y,
+            a * self.z + b * other.z
+        ).normalized()
+    }
+}
+
+// Composition: `a * b` rotates by `b`, then by `a`.
+impl ops::Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        Quaternion::new(
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w
+        )
+    }
+}
+
+
+// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Aabb {
+    pub fn new(min: Point, max: Point) -> Aabb {
+        Aabb { min: min, max: max }
+    }
+
+    // The smallest box containing all of `points`, or None if there are none.
+    pub fn from_points(points: &[Point]) -> Option<Aabb> {
+        let first = match points.first() {
+            Some(&p) => p,
+            None => return None,
+        };
+        let mut aabb = Aabb::new(first, first);
+        for p in &points[1 ..] {
+            aabb.min = pt![
+                aabb.min.x.min(p.x),
+                aabb.min.y.min(p.y),
+                aabb.min.z.min(p.z)
+            ];
+            aabb.max = pt![
+                aabb.max.x.max(p.x),
+                aabb.max.y.max(p.y),
+                aabb.max.z.max(p.z)
+            ];
+        }
+        Some(aabb)
+    }
+
+    pub fn center(&self) -> Point {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Point {
+        self.max - self.min
+    }
+
+    // Corner i takes its x from `max` if bit 0 of i is set, else from `min`,
+    // and likewise y from bit 1 and z from bit 2.
+    pub fn corners(&self) -> [Point; 8] {
+        let (lo, hi) = (self.min, self.max);
+        [
+            pt![lo.x, lo.y, lo.z],
+            pt![hi.x, lo.y, lo.z],
+            pt![lo.x, hi.y, lo.z],
+            pt![hi.x, hi.y, lo.z],
+            pt![lo.x, lo.y, hi.z],
+            pt![hi.x, lo.y, hi.z],
+            pt![lo.x, hi.y, hi.z],
+            pt![hi.x, hi.y, hi.z],
+        ]
+    }
+}
+
+
+// The volume a camera sees, as the eight corners of its clip-space cube
+// [-1, 1]^3 carried back into world space.
+#[derive(Debug, Clone, Copy)]
+pub struct Frustum {
+    // Indexed like `Aabb::corners`: bit 0 picks the right side, bit 1 the
+    // top and bit 2 the far plane.
+    pub corners: [Point; 8],
+}
+
+impl Frustum {
+    // The frustum of the projection-view matrix `view_projection`, or None if
+    // it is singular.
+    pub fn from_view_projection(view_projection: Transform) -> Option<Frustum> {
+        view_projection.inverse().map(|inverse| {
+            let cube = Aabb::new(pt![-1., -1., -1.], pt![1., 1., 1.]);
+            let mut corners = cube.corners();
+            for c in corners.iter_mut() {
+                *c = *c * inverse;
+            }
+            Frustum { corners: corners }
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+    use std::f64::consts::PI;
+
+    fn assert_close(a: Point, b: Point) {
+        assert!(
+            (a - b).magnitude() < 1e-9,
+            "{:?} is not {:?}", a, b
+        );
+    }
+
+    #[test]
+    fn scale_uniform_scales_every_axis() {
+        let s = Transform::scale_uniform(2.5);
+        assert_close(pt![1., -2., 4.] * s, pt![2.5, -5., 10.]);
+        assert_close(
+            pt![3., 1., -1.] * s,
+            pt![3., 1., -1.] * Transform::scale(2.5, 2.5, 2.5)
+        );
+        assert_close(pt![0., 0., 0.] * s, pt![0., 0., 0.]);
+    }
+
+    fn assert_near(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} is not {}", a, b);
+    }
+
+    #[test]
+    fn perspective_opengl_depth_range() {
+        let (near, far) = (0.5, 20.);
+        let t = Transform::perspective_opengl(1., 1.5, near, far);
+        assert_near((pt![0., 0., -near] * t).z, -1.);
+        assert_near((pt![0., 0., -far] * t).z, 1.);
+        let mid = (pt![0., 0., -2.] * t).z;
+        assert!(-1. < mid && mid < 1.);
+
+        // The corners of the near plane land on the corners of the screen.
+        let half_h = (0.5f64).tan() * near;
+        let corner = pt![1.5 * half_h, half_h, -near] * t;
+        assert_close(corner, pt![1., 1., -1.]);
+    }
+
+    #[test]
+    fn perspective_d3d_depth_range() {
+        let (near, far) = (0.5, 20.);
+        let t = Transform::perspective_d3d(1., 1.5, near, far);
+        assert_near((pt![0., 0., -near] * t).z, 0.);
+        assert_near((pt![0., 0., -far] * t).z, 1.);
+        let mid = (pt![0., 0., -2.] * t).z;
+        assert!(0. < mid && mid < 1.);
+
+        let half_h = (0.5f64).tan() * far;
+        let corner = pt![-1.5 * half_h, -half_h, -far] * t;
+        assert_close(corner, pt![-1., -1., 1.]);
+    }
+
+    fn assert_transform_close(a: Transform, b: Transform) {
+        for (ra, rb) in a.data.iter().zip(b.data.iter()) {
+            for (&x, &y) in ra.iter().zip(rb.iter()) {
+                assert!((x - y).abs() < 1e-9, "{:?} is not {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn from_parts_then_decompose() {
+        let translation = pt![1., -2., 3.5];
+        let rotation = Quaternion::from_axis_angle(pt![1., 2., -1.], 0.8);
+        let scale = pt![2., 0.5, 3.];
+        let t = Transform::from_parts(translation, rotation, scale);
+
+        let (t2, r2, s2) = t.decompose();
+        assert_close(t2, translation);
+        assert_close(s2, scale);
+        // q and -q are the same rotation.
+        assert!((r2.dot(rotation).abs() - 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn decompose_then_from_parts() {
+        let t = Transform::compose(&[
+            Transform::translate(pt![-4., 0., 2.]),
+            Transform::rotate_y(1.2),
+            Transform::rotate_x(-0.3),
+            Transform::scale(1.5, 2., 0.25),
+        ]);
+        let (translation, rotation, scale) = t.decompose();
+        assert_transform_close(
+            Transform::from_parts(translation, rotation, scale),
+            t
+        );
+
+        // A mirror comes back as a negative x scale.
+        let mirror = Transform::scale(1., -2., 3.);
+        let (translation, rotation, scale) = mirror.decompose();
+        assert!(scale.x < 0.);
+        assert_transform_close(
+            Transform::from_parts(translation, rotation, scale),
+            mirror
+        );
+    }
+
+    #[test]
+    fn subdivide_splits_at_midpoints() {
+        let t = trigon![pt![0., 0., 0.], pt![4., 0., 0.], pt![0., 2., 6.]];
+        let parts = t.subdivide();
+        let m12 = pt![2., 0., 0.];
+        let m23 = pt![2., 1., 3.];
+        let m31 = pt![0., 1., 3.];
+        let expected = [
+            [t.p1, m12, m31],
+            [m12, t.p2, m23],
+            [m31, m23, t.p3],
+            [m12, m23, m31],
+        ];
+        for (part, corners) in parts.iter().zip(expected.iter()) {
+            for (&p, &q) in part.to_arr().iter().zip(corners.iter()) {
+                assert_close(p, q);
+            }
+            // Each quarter keeps the winding and a quarter of the area.
+            assert!(part.normal().dot(t.normal()) > 0.);
+            assert!((part.area() * 4. - t.area()).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn rotate_axis_angle_matches_the_axis_rotations() {
+        for &angle in &[0.3, -1.1, 2.5, PI] {
+            assert_transform_close(
+                Transform::rotate_axis_angle(pt![0., 1., 0.], angle),
+                Transform::rotate_y(angle)
+            );
+            assert_transform_close(
+                Transform::rotate_axis_angle(pt![1., 0., 0.], -angle),
+                Transform::rotate_x(angle)
+            );
+            assert_transform_close(
+                Transform::rotate_axis_angle(pt![0., 0., 3.], -angle),
+                Transform::rotate_z(angle)
+            );
+        }
+
+        // A quarter turn about z takes x to y; points on the axis stay put.
+        let t = Transform::rotate_axis_angle(pt![0., 0., 1.], FRAC_PI_2);
+        assert_close(pt![1., 0., 0.] * t, pt![0., 1., 0.]);
+        assert_close(pt![0., 0., 2.] * t, pt![0., 0., 2.]);
+    }
+
+    #[test]
+    fn shear_xy_moves_x_axis_points_in_y() {
+        let t = Transform::shear(0.5, 0., 0., 0., 0., 0.);
+        assert_close(pt![2., 0., 0.] * t, pt![2., 1., 0.]);
+        assert_close(pt![-4., 0., 0.] * t, pt![-4., -2., 0.]);
+        // Points off the x axis only gain the x part.
+        assert_close(pt![2., 3., 5.] * t, pt![2., 4., 5.]);
+        assert_close(pt![0., 3., 5.] * t, pt![0., 3., 5.]);
+
+        // Every other factor leaves the x axis where it is in y.
+        let t = Transform::shear(0., 1., 2., 3., 4., 5.);
+        assert_close(pt![2., 0., 0.] * t, pt![2., 0., 2.]);
+    }
+
+    #[test]
+    fn reflect_twice_is_the_identity() {
+        for &n in &[pt![0., 1., 0.], pt![1., -2., 0.5], pt![3., 3., 3.]] {
+            let m = Transform::reflect(n);
+            assert_transform_close(m * m, Transform::identity());
+        }
+    }
+
+    #[test]
+    fn reflect_known_points() {
+        // Across the plane y = 0, which needs no unit normal.
+        let m = Transform::reflect(pt![0., 5., 0.]);
+        assert_close(pt![1., 2., 3.] * m, pt![1., -2., 3.]);
+        assert_close(pt![4., 0., -1.] * m, pt![4., 0., -1.]);
+
+        // Across x = y, which swaps the two.
+        let m = Transform::reflect(pt![1., -1., 0.]);
+        assert_close(pt![3., 1., 7.] * m, pt![1., 3., 7.]);
+
+        // A zero normal leaves points alone.
+        let m = Transform::reflect(pt![0., 0., 0.]);
+        assert_close(pt![3., 1., 7.] * m, pt![3., 1., 7.]);
+    }
+
+    #[test]
+    fn compose_is_associative() {
+        let (a, b, c, d) = (
+            Transform::translate(pt![1., -2., 3.]),
+            Transform::rotate_x(0.7),
+            Transform::scale(2., 0.5, -1.),
+            Transform::shear(0.3, 0., 0., 1., 0., 0.),
+        );
+        let all = Transform::compose(&[a, b, c, d]);
+        assert_transform_close(all, a * b * c * d);
+        assert_transform_close(all, (a * b) * (c * d));
+        assert_transform_close(all, a * (b * (c * d)));
+        assert_transform_close(
+            all,
+            Transform::compose(&[
+                Transform::compose(&[a, b]),
+                Transform::compose(&[c, d]),
+            ])
+        );
+        assert_transform_close(
+            Transform::compose_inv(&[a, b, c, d]),
+            d * c * b * a
+        );
+
+        // The last transform is applied first, and the first by compose_inv.
+        let p = pt![1., 1., 1.];
+        let t = Transform::translate(pt![1., 0., 0.]);
+        let s = Transform::scale_uniform(2.);
+        assert_close(p * Transform::compose(&[t, s]), pt![3., 2., 2.]);
+        assert_close(p * Transform::compose_inv(&[t, s]), pt![4., 2., 2.]);
+        assert_transform_close(Transform::compose(&[]), Transform::identity());
+    }
+}
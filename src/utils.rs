@@ -5,3 +5,38 @@ pub fn clamp<Num: PartialOrd>(x: Num, min: Num, max: Num)
     else if x > max { max }
     else { x }
 }
+
+// One-dimensional convolution over `lines` lines of `len` samples each, with
+// `kernel` centered on each sample. Samples within a line are `step` apart
+// and lines start `line_step` apart. Samples past the ends of a line are
+// clamped.
+pub fn convolve_pass(
+    src: &[(f32, f32, f32)],
+    len: usize,
+    lines: usize,
+    step: usize,
+    line_step: usize,
+    kernel: &[f32],
+)
+    -> Vec<(f32, f32, f32)>
+{
+    let r = (kernel.len() / 2) as i64;
+    let mut out = vec![(0., 0., 0.); src.len()];
+
+    for line in 0 .. lines {
+        let base = line * line_step;
+        for i in 0 .. len as i64 {
+            let mut sum = (0., 0., 0.);
+            for (k, &weight) in (i - r ..).zip(kernel.iter()) {
+                let k = clamp(k, 0, len as i64 - 1) as usize;
+                let (sr, sg, sb) = src[base + k * step];
+                sum.0 += sr * weight;
+                sum.1 += sg * weight;
+                sum.2 += sb * weight;
+            }
+            out[base + i as usize * step] = sum;
+        }
+    }
+
+    out
+}
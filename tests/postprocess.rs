@@ -0,0 +1,116 @@
+extern crate rusterize;
+
+use rusterize::pixel;
+use rusterize::pixel::Pixel;
+use rusterize::postprocess::GrayscaleFilter;
+use rusterize::postprocess::InvertFilter;
+use rusterize::postprocess::PostProcess;
+use rusterize::renderer::Renderer;
+use rusterize::screen::SliceFormat;
+use rusterize::screen::SliceScreen;
+
+
+const W: u32 = 4;
+const H: u32 = 3;
+
+// Clears a frame to `color`, displays it through `passes` and returns the
+// first pixel that reached the screen, along with whether the renderer's own
+// frame kept `color`.
+fn display_through(color: Pixel, passes: Vec<Box<PostProcess>>)
+    -> (Pixel, bool)
+{
+    let mut buf = [0u8; (W * H * 3) as usize];
+    let untouched = {
+        let screen = SliceScreen::new(
+            &mut buf,
+            W,
+            H,
+            3 * W as usize,
+            SliceFormat::Rgb
+        ).unwrap();
+        let mut renderer = Renderer::new(screen);
+        renderer.set_clear_color(color);
+        renderer.clear();
+        renderer.set_post_processes(passes);
+        renderer.display().unwrap();
+        renderer.snapshot().pixels.iter().all(|&p| p == color)
+    };
+    assert!(buf.chunks(3).all(|p| p == &buf[.. 3]));
+    ((buf[0], buf[1], buf[2]), untouched)
+}
+
+#[test]
+fn no_passes_display_the_frame() {
+    let (shown, _) = display_through((10, 100, 250), Vec::new());
+    assert_eq!(shown, (10, 100, 250));
+}
+
+#[test]
+fn invert_filter() {
+    let (shown, untouched) =
+        display_through((10, 100, 250), vec![Box::new(InvertFilter)]);
+    assert_eq!(shown, (245, 155, 5));
+    assert!(untouched);
+}
+
+#[test]
+fn grayscale_filter() {
+    let (shown, untouched) =
+        display_through(pixel::RED, vec![Box::new(GrayscaleFilter)]);
+    assert_eq!(shown, (76, 76, 76));
+    assert!(untouched);
+}
+
+#[test]
+fn passes_chain() {
+    // Inverting red gives cyan, whose gray is 179.
+    let (shown, _) = display_through(
+        pixel::RED,
+        vec![Box::new(InvertFilter), Box::new(GrayscaleFilter)]
+    );
+    assert_eq!(shown, (179, 179, 179));
+
+    let (shown, _) = display_through(
+        (10, 100, 250),
+        vec![Box::new(InvertFilter), Box::new(InvertFilter)]
+    );
+    assert_eq!(shown, (10, 100, 250));
+}
+
+#[test]
+fn pipeline_can_be_replaced_and_cleared() {
+    let mut buf = [0u8; (W * H * 3) as usize];
+    {
+        let screen = SliceScreen::new(
+            &mut buf,
+            W,
+            H,
+            3 * W as usize,
+            SliceFormat::Rgb
+        ).unwrap();
+        let mut renderer = Renderer::new(screen);
+        renderer.set_clear_color(pixel::RED);
+        renderer.clear();
+        renderer.add_post_process(Box::new(InvertFilter));
+        renderer.set_post_processes(vec![Box::new(GrayscaleFilter)]);
+        renderer.display().unwrap();
+    }
+    assert_eq!(&buf[.. 3], &[76, 76, 76]);
+
+    {
+        let screen = SliceScreen::new(
+            &mut buf,
+            W,
+            H,
+            3 * W as usize,
+            SliceFormat::Rgb
+        ).unwrap();
+        let mut renderer = Renderer::new(screen);
+        renderer.set_clear_color(pixel::RED);
+        renderer.clear();
+        renderer.add_post_process(Box::new(InvertFilter));
+        renderer.clear_post_processes();
+        renderer.display().unwrap();
+    }
+    assert_eq!(&buf[.. 3], &[255, 0, 0]);
+}